@@ -0,0 +1,47 @@
+use std::time::Instant;
+
+use core::{mov::gen::generate_legal_moves, position::Position};
+
+const DEPTH: u8 = 4;
+const FEN: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+/// Walks the move tree by copying the position at every node.
+fn copy_make(pos: &Position, depth: u8) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let mut nodes = 0;
+    for mov in generate_legal_moves(pos).into_iter() {
+        let (next_pos, _) = pos.apply_move(&mov);
+        nodes += copy_make(&next_pos, depth - 1);
+    }
+    nodes
+}
+
+/// Walks the move tree by making and unmaking moves on a single position.
+fn make_unmake(pos: &mut Position, depth: u8) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let mut nodes = 0;
+    for mov in generate_legal_moves(pos).into_iter() {
+        let undo = pos.make_move(mov);
+        nodes += make_unmake(pos, depth - 1);
+        pos.unmake_move(mov, undo);
+    }
+    nodes
+}
+
+fn main() {
+    let mut pos = Position::from_fen(FEN.into()).expect("couldn't parse fen");
+
+    let start = Instant::now();
+    let nodes = copy_make(&pos, DEPTH);
+    println!("copy-make:   {} nodes in {:?}", nodes, start.elapsed());
+
+    let start = Instant::now();
+    let nodes = make_unmake(&mut pos, DEPTH);
+    println!("make-unmake: {} nodes in {:?}", nodes, start.elapsed());
+}
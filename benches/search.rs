@@ -0,0 +1,115 @@
+use std::{sync::atomic::AtomicBool, time::Instant};
+
+use core::{
+    position::Position,
+    search::{
+        eval::material_evaluator,
+        limits::SearchLimits,
+        options::SearchOptions,
+        tree::iterative_deepening,
+        tt::{TranspositionTable, DEFAULT_HASH_MB},
+    },
+};
+
+const DEPTH: u8 = 6;
+
+/// The standard perft test positions.
+const FENS: [&str; 6] = [
+    Position::STANDARD_FEN,
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+];
+
+/// Searches `pos` to `DEPTH` with a table of `size_mb` and `options`, and prints the node count.
+fn search(name: &str, pos: &Position, size_mb: usize, options: &SearchOptions) -> u64 {
+    let start = Instant::now();
+    let result = iterative_deepening(
+        pos,
+        &mut material_evaluator,
+        &mut TranspositionTable::new(size_mb),
+        &SearchLimits::depth(DEPTH),
+        options,
+        &AtomicBool::new(false),
+        |_| {},
+    );
+    println!(
+        "  {:<10} {:>10} nodes in {:?}",
+        name,
+        result.nodes,
+        start.elapsed()
+    );
+    result.nodes
+}
+
+fn main() {
+    let none = SearchOptions::none();
+    // plain alpha-beta, then with a table, each technique on its own, and all of them
+    let configs = [
+        ("no tt:", 0, none.clone()),
+        ("tt:", DEFAULT_HASH_MB, none.clone()),
+        (
+            "pvs:",
+            DEFAULT_HASH_MB,
+            SearchOptions {
+                pvs: true,
+                ..none.clone()
+            },
+        ),
+        (
+            "null move:",
+            DEFAULT_HASH_MB,
+            SearchOptions {
+                null_move: true,
+                ..none.clone()
+            },
+        ),
+        (
+            "lmr:",
+            DEFAULT_HASH_MB,
+            SearchOptions {
+                lmr: true,
+                ..none.clone()
+            },
+        ),
+        (
+            "see pruning:",
+            DEFAULT_HASH_MB,
+            SearchOptions {
+                see_pruning: true,
+                ..none.clone()
+            },
+        ),
+        (
+            "pruning:",
+            DEFAULT_HASH_MB,
+            SearchOptions {
+                pvs: true,
+                futility: true,
+                reverse_futility: true,
+                razoring: true,
+                ..none.clone()
+            },
+        ),
+        ("all:", DEFAULT_HASH_MB, SearchOptions::default()),
+    ];
+
+    for fen in FENS {
+        let pos = Position::from_fen(fen.into()).expect("couldn't parse fen");
+        println!("{}", fen);
+
+        let mut baseline = None;
+        for (name, size_mb, options) in &configs {
+            let nodes = search(name, &pos, *size_mb, options);
+            match baseline {
+                None => baseline = Some(nodes),
+                Some(baseline) => println!(
+                    "  {:.1}% fewer nodes",
+                    100.0 - nodes as f64 * 100.0 / baseline as f64
+                ),
+            }
+        }
+    }
+}
@@ -0,0 +1,360 @@
+use std::{
+    io::{self, BufRead},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use core::{
+    position::Position,
+    search::{
+        eval::PstEvaluator,
+        limits::SearchLimits,
+        mate_distance,
+        options::SearchOptions,
+        trace::EvalTrace,
+        tree::{iterative_deepening, SearchInfo},
+        tt::{Bound, TranspositionTable, DEFAULT_HASH_MB},
+        Score,
+    },
+};
+
+/// Parses the value of a `check` option.
+fn parse_check(name: &str, value: &str) -> Result<bool, String> {
+    match value {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err(format!("invalid {} value: {}", name, value)),
+    }
+}
+
+/// Parses the arguments of a `go` command.
+fn parse_limits(args: &[&str]) -> Result<SearchLimits, String> {
+    let mut limits = SearchLimits::default();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        if *arg == "infinite" {
+            limits.infinite = true;
+            continue;
+        }
+
+        let value = args
+            .next()
+            .ok_or_else(|| format!("missing value for {}", arg))?;
+        let number = || {
+            value
+                .parse::<u64>()
+                .map_err(|_| format!("invalid value for {}: {}", arg, value))
+        };
+        let millis = || number().map(Duration::from_millis);
+
+        match *arg {
+            "depth" => {
+                limits.depth = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("invalid depth: {}", value))?,
+                )
+            }
+            "nodes" => limits.nodes = Some(number()?),
+            "movetime" => limits.movetime = Some(millis()?),
+            "wtime" => limits.wtime = Some(millis()?),
+            "btime" => limits.btime = Some(millis()?),
+            "winc" => limits.winc = Some(millis()?),
+            "binc" => limits.binc = Some(millis()?),
+            "movestogo" => limits.movestogo = Some(number()? as u32),
+            "mate" => limits.mate = Some(number()? as u32),
+            _ => return Err(format!("unknown go parameter: {}", arg)),
+        }
+    }
+
+    Ok(limits)
+}
+
+/// A search running in the background.
+struct Search {
+    handle: JoinHandle<()>,
+    /// Tells the search to stop as soon as possible.
+    stop: Arc<AtomicBool>,
+    /// Tells an infinite search that it may report its move.
+    stopped: Sender<()>,
+}
+
+/// The largest transposition table the `Hash` option allows, in megabytes.
+const MAX_HASH_MB: usize = 4096;
+
+/// Reaches one of the switches of `SearchOptions`.
+type Switch = fn(&mut SearchOptions) -> &mut bool;
+
+/// The search options that are switched on and off by `check` options, by option name.
+const CHECK_OPTIONS: &[(&str, Switch)] = &[
+    ("PVS", |options| &mut options.pvs),
+    ("NullMove", |options| &mut options.null_move),
+    ("LMR", |options| &mut options.lmr),
+    ("Aspiration", |options| &mut options.aspiration),
+    ("CheckExtensions", |options| &mut options.check_extensions),
+    ("SingularExtensions", |options| {
+        &mut options.singular_extensions
+    }),
+    ("Futility", |options| &mut options.futility),
+    ("ReverseFutility", |options| &mut options.reverse_futility),
+    ("Razoring", |options| &mut options.razoring),
+    ("SeePruning", |options| &mut options.see_pruning),
+];
+
+struct Engine {
+    position: Position,
+    /// Shared with the search thread, which holds the lock while it runs.
+    tt: Arc<Mutex<TranspositionTable>>,
+    options: SearchOptions,
+    search: Option<Search>,
+}
+
+impl Engine {
+    fn new() -> Engine {
+        Engine {
+            position: Engine::start_position(),
+            tt: Arc::new(Mutex::new(TranspositionTable::new(DEFAULT_HASH_MB))),
+            options: SearchOptions::default(),
+            search: None,
+        }
+    }
+
+    fn start_position() -> Position {
+        Position::from_fen(Position::STANDARD_FEN.into()).expect("standard fen is valid")
+    }
+
+    /// Handles one line of input. Returns false when the engine should quit.
+    fn handle(&mut self, line: &str) -> bool {
+        let args: Vec<&str> = line.split_whitespace().collect();
+        let Some((command, args)) = args.split_first() else {
+            return true;
+        };
+
+        let result = match *command {
+            "uci" => {
+                println!("id name cheddar {}", env!("CARGO_PKG_VERSION"));
+                println!("id author the rust-cheddar authors");
+                println!(
+                    "option name Hash type spin default {} min 1 max {}",
+                    DEFAULT_HASH_MB, MAX_HASH_MB
+                );
+                let mut defaults = SearchOptions::default();
+                for param in defaults.params() {
+                    println!(
+                        "option name {} type spin default {} min {} max {}",
+                        param.name, param.value, param.min, param.max
+                    );
+                }
+                for (name, field) in CHECK_OPTIONS {
+                    println!(
+                        "option name {} type check default {}",
+                        name,
+                        field(&mut defaults)
+                    );
+                }
+                println!("uciok");
+                Ok(())
+            }
+            "isready" => {
+                println!("readyok");
+                Ok(())
+            }
+            "ucinewgame" => {
+                self.stop();
+                self.position = Engine::start_position();
+                self.tt.lock().expect("search thread panicked").clear();
+                Ok(())
+            }
+            "position" => self.set_position(args),
+            "go" => self.go(args),
+            "stop" => {
+                self.stop();
+                Ok(())
+            }
+            "quit" => {
+                self.stop();
+                return false;
+            }
+            "setoption" => self.set_option(args),
+            "eval" => self.eval(args),
+            // debug output is always off
+            "debug" => Ok(()),
+            _ => Err(format!("unknown command: {}", command)),
+        };
+
+        if let Err(e) = result {
+            println!("info string {}", e);
+        }
+
+        true
+    }
+
+    /// Handles `setoption name <name> value <value>`.
+    fn set_option(&mut self, args: &[&str]) -> Result<(), String> {
+        let (name, value) = match args {
+            ["name", name @ .., "value", value] => (name.join(" "), *value),
+            _ => return Err(format!("invalid option: {}", args.join(" "))),
+        };
+
+        // option names aren't case sensitive
+        let is = |option: &str| option.eq_ignore_ascii_case(&name);
+        if is("Hash") {
+            let size = value
+                .parse::<usize>()
+                .ok()
+                .filter(|size| (1..=MAX_HASH_MB).contains(size))
+                .ok_or_else(|| format!("invalid Hash size: {}", value))?;
+            self.stop();
+            self.tt.lock().expect("search thread panicked").resize(size);
+            return Ok(());
+        }
+        if self.options.params().iter().any(|param| is(&param.name)) {
+            return value
+                .parse::<Score>()
+                .ok()
+                .and_then(|value| self.options.set_param(&name, value).ok())
+                .ok_or_else(|| format!("invalid {}: {}", name, value));
+        }
+        match CHECK_OPTIONS.iter().find(|(option, _)| is(option)) {
+            Some((_, field)) => {
+                *field(&mut self.options) = parse_check(&name, value)?;
+                Ok(())
+            }
+            None => Err(format!("unknown option: {}", name)),
+        }
+    }
+
+    /// Handles `position startpos|fen <fen> [moves <move>...]`.
+    fn set_position(&mut self, args: &[&str]) -> Result<(), String> {
+        let moves_index = args
+            .iter()
+            .position(|arg| *arg == "moves")
+            .unwrap_or(args.len());
+        let (setup, moves) = args.split_at(moves_index);
+
+        let mut position = match setup.split_first() {
+            Some((&"startpos", [])) => Engine::start_position(),
+            Some((&"fen", fen)) => Position::from_fen(fen.join(" "))?,
+            _ => return Err(format!("invalid position: {}", args.join(" "))),
+        };
+
+        for mov in moves.iter().skip(1) {
+            let mov = position.parse_uci_move(mov)?;
+            position.make_move(mov);
+        }
+
+        self.position = position;
+        Ok(())
+    }
+
+    /// Handles `eval`, printing how the current position is evaluated as a table, or as JSON
+    /// with `eval json`.
+    fn eval(&self, args: &[&str]) -> Result<(), String> {
+        let trace = EvalTrace::new(&mut PstEvaluator, &self.position);
+        match args {
+            [] => println!("{}", trace),
+            ["json"] => println!("{}", trace.to_json()),
+            _ => return Err(format!("invalid eval arguments: {}", args.join(" "))),
+        }
+        Ok(())
+    }
+
+    /// Handles `go`, starting a search in the background.
+    fn go(&mut self, args: &[&str]) -> Result<(), String> {
+        let limits = parse_limits(args)?;
+        self.stop();
+
+        let position = self.position.clone();
+        let tt = self.tt.clone();
+        let options = self.options.clone();
+        let stop = Arc::new(AtomicBool::new(false));
+        let search_stop = stop.clone();
+        let (stopped, wait_for_stop) = mpsc::channel();
+
+        let handle = thread::spawn(move || {
+            let mut tt = tt.lock().expect("search thread panicked");
+            let result = iterative_deepening(
+                &position,
+                &mut PstEvaluator,
+                &mut tt,
+                &limits,
+                &options,
+                &search_stop,
+                print_info,
+            );
+
+            // the null move tells the GUI there is nothing to play
+            let best = result
+                .best_move()
+                .map_or("0000".to_string(), |mov| mov.to_uci());
+
+            // an infinite search may only report its move once it is told to stop
+            if limits.infinite {
+                let _ = wait_for_stop.recv();
+            }
+            println!("bestmove {}", best);
+        });
+
+        self.search = Some(Search {
+            handle,
+            stop,
+            stopped,
+        });
+        Ok(())
+    }
+
+    /// Stops the running search, if any, and waits for it to report its move.
+    fn stop(&mut self) {
+        if let Some(search) = self.search.take() {
+            search.stop.store(true, Ordering::Relaxed);
+            let _ = search.stopped.send(());
+            search.handle.join().expect("search thread panicked");
+        }
+    }
+}
+
+fn print_info(info: &SearchInfo) {
+    let elapsed = info.elapsed.as_millis();
+    let nps = info.nodes as u128 * 1000 / elapsed.max(1);
+    let pv: Vec<String> = info.pv.iter().map(|mov| mov.to_uci()).collect();
+    let mut score = match mate_distance(info.score) {
+        Some(moves) => format!("mate {}", moves),
+        None => format!("cp {}", info.score),
+    };
+    match info.bound {
+        Bound::Exact => {}
+        Bound::Lower => score.push_str(" lowerbound"),
+        Bound::Upper => score.push_str(" upperbound"),
+    }
+    println!(
+        "info depth {} score {} nodes {} nps {} time {} hashfull {} pv {}",
+        info.depth,
+        score,
+        info.nodes,
+        nps,
+        elapsed,
+        info.hashfull,
+        pv.join(" ")
+    );
+}
+
+fn main() {
+    let mut engine = Engine::new();
+
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if !engine.handle(&line) {
+            return;
+        }
+    }
+
+    engine.stop();
+}
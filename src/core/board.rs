@@ -14,6 +14,19 @@ impl Castles {
     pub const BLACK_QUEEN: Bits = 0x0400000000000000;
     pub const WHITE_KING: Bits = 0x0000000000000040;
     pub const WHITE_QUEEN: Bits = 0x0000000000000004;
+
+    /// Returns the castling rights that are lost when a piece moves from or to the given square.
+    pub fn revoked_by(index: BoardIndex) -> Bits {
+        match index {
+            0 => Castles::WHITE_QUEEN,
+            4 => Castles::WHITE_KING | Castles::WHITE_QUEEN,
+            7 => Castles::WHITE_KING,
+            56 => Castles::BLACK_QUEEN,
+            60 => Castles::BLACK_KING | Castles::BLACK_QUEEN,
+            63 => Castles::BLACK_KING,
+            _ => 0,
+        }
+    }
 }
 
 /// Returns the bit at the specified index (exactly 0 or 1).
@@ -52,7 +65,7 @@ impl Board {
         let mut out = board;
         out = ((out >> 8) & K1) | ((out & K1) << 8);
         out = ((out >> 16) & K2) | ((out & K2) << 16);
-        out = (out >> 32) | (out << 32);
+        out = out.rotate_left(32);
         out
     }
 
@@ -77,7 +77,7 @@ impl SlideFill {
 
     pub fn west_occl(board: Bits, open: Bits) -> Bits {
         let mut gen = board;
-        let mut pro = open;
+        let mut pro = open & !H_FILE;
 
         ks_dir_fill_neg(&mut gen, &mut pro, 1);
         gen
@@ -0,0 +1,213 @@
+use crate::{
+    board::{Bits, Board},
+    mov::{
+        gen::{generate_legal_moves, is_in_check},
+        Move,
+    },
+    position::{Color, Position},
+};
+
+const LIGHT_SQUARES: Bits = 0x55aa55aa55aa55aa;
+
+/// The reason a game ended, or could be ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Termination {
+    Checkmate,
+    Stalemate,
+    InsufficientMaterial,
+    /// The same position occurred three times. A draw may be claimed.
+    ThreefoldRepetition,
+    /// The same position occurred five times. The game is drawn automatically.
+    FivefoldRepetition,
+    /// Fifty moves were played without a capture or pawn move. A draw may be claimed.
+    FiftyMoveRule,
+    /// Seventy-five moves were played without a capture or pawn move. The game is drawn
+    /// automatically.
+    SeventyFiveMoveRule,
+}
+
+/// The outcome of a game.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameResult {
+    Ongoing,
+    Win(Color, Termination),
+    Draw(Termination),
+}
+
+/// A game of chess: a starting position and the moves played from it.
+#[derive(Clone, Debug)]
+pub struct Game {
+    moves: Vec<Move>,
+    /// Every position in the game, starting with the initial one.
+    /// `positions[i + 1]` is the position after `moves[i]`.
+    positions: Vec<Position>,
+}
+
+impl Game {
+    /// Starts a new game from the given position.
+    pub fn new(pos: Position) -> Game {
+        Game {
+            moves: Vec::new(),
+            positions: vec![pos],
+        }
+    }
+
+    /// Starts a new game from a FEN string.
+    pub fn from_fen(fen: String) -> Result<Game, String> {
+        Ok(Game::new(Position::from_fen(fen)?))
+    }
+
+    /// Returns the position the game started from.
+    pub fn start(&self) -> &Position {
+        &self.positions[0]
+    }
+
+    /// Returns the current position.
+    pub fn position(&self) -> &Position {
+        self.positions.last().expect("a game always has a position")
+    }
+
+    /// Returns the moves played so far.
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    /// Returns every position in the game, starting with the initial one.
+    pub fn positions(&self) -> &[Position] {
+        &self.positions
+    }
+
+    /// Plays a move, if it is legal and the game hasn't ended.
+    pub fn play(&mut self, mov: Move) -> Result<(), String> {
+        if self.result() != GameResult::Ongoing {
+            return Err("the game is already over".to_string());
+        }
+
+        if !generate_legal_moves(self.position()).contains(&mov) {
+            return Err(format!(
+                "{} is not a legal move in {}",
+                mov,
+                self.position().to_fen()
+            ));
+        }
+
+        let (pos, _) = self.position().apply_move(&mov);
+        self.moves.push(mov);
+        self.positions.push(pos);
+        Ok(())
+    }
+
+    /// Takes back the last move, returning it.
+    pub fn undo(&mut self) -> Option<Move> {
+        let mov = self.moves.pop()?;
+        self.positions.pop();
+        Some(mov)
+    }
+
+    /// Returns the number of times the current position has occurred, including now.
+    /// Positions are compared by piece placement, side to move, castles and en passant square.
+    pub fn repetitions(&self) -> usize {
+        let pos = self.position();
+
+        // only positions since the last capture or pawn move can repeat, and only those with
+        // the same side to move
+        let reversible = (pos.halfmove_clock as usize).min(self.positions.len() - 1);
+        self.positions[self.positions.len() - 1 - reversible..]
+            .iter()
+            .rev()
+            .step_by(2)
+            .filter(|other| other.hash == pos.hash && Game::same_position(pos, other))
+            .count()
+    }
+
+    pub fn is_checkmate(&self) -> bool {
+        is_in_check(self.position()) && generate_legal_moves(self.position()).count() == 0
+    }
+
+    pub fn is_stalemate(&self) -> bool {
+        !is_in_check(self.position()) && generate_legal_moves(self.position()).count() == 0
+    }
+
+    pub fn is_threefold_repetition(&self) -> bool {
+        self.repetitions() >= 3
+    }
+
+    pub fn is_fivefold_repetition(&self) -> bool {
+        self.repetitions() >= 5
+    }
+
+    pub fn is_fifty_move_rule(&self) -> bool {
+        self.position().halfmove_clock >= 100
+    }
+
+    pub fn is_seventy_five_move_rule(&self) -> bool {
+        self.position().halfmove_clock >= 150
+    }
+
+    /// Returns true if neither side has enough material to deliver checkmate: bare kings,
+    /// a single minor piece, or only bishops that all stand on the same color.
+    pub fn is_insufficient_material(&self) -> bool {
+        let pos = self.position();
+
+        let heavy = pos.white.pawns
+            | pos.white.rooks
+            | pos.white.queens
+            | pos.black.pawns
+            | pos.black.rooks
+            | pos.black.queens;
+        if heavy != 0 {
+            return false;
+        }
+
+        let knights = pos.white.knights | pos.black.knights;
+        let bishops = pos.white.bishops | pos.black.bishops;
+        if Board::get_number_of_pieces(knights | bishops) <= 1 {
+            return true;
+        }
+
+        knights == 0 && (bishops & LIGHT_SQUARES == 0 || bishops & !LIGHT_SQUARES == 0)
+    }
+
+    /// Returns the result of the game, considering only the endings that don't need to be
+    /// claimed. See `claimable_draw` for the threefold repetition and fifty-move rules.
+    pub fn result(&self) -> GameResult {
+        if generate_legal_moves(self.position()).count() == 0 {
+            return if is_in_check(self.position()) {
+                GameResult::Win(self.position().turn.opposite(), Termination::Checkmate)
+            } else {
+                GameResult::Draw(Termination::Stalemate)
+            };
+        }
+
+        if self.is_fivefold_repetition() {
+            GameResult::Draw(Termination::FivefoldRepetition)
+        } else if self.is_seventy_five_move_rule() {
+            GameResult::Draw(Termination::SeventyFiveMoveRule)
+        } else if self.is_insufficient_material() {
+            GameResult::Draw(Termination::InsufficientMaterial)
+        } else {
+            GameResult::Ongoing
+        }
+    }
+
+    /// Returns the reason a draw may be claimed in the current position, if there is one.
+    pub fn claimable_draw(&self) -> Option<Termination> {
+        if self.is_threefold_repetition() {
+            Some(Termination::ThreefoldRepetition)
+        } else if self.is_fifty_move_rule() {
+            Some(Termination::FiftyMoveRule)
+        } else {
+            None
+        }
+    }
+
+    /// Returns true if both positions count as the same position for repetitions. An en passant
+    /// square only tells them apart if it can be captured on.
+    fn same_position(a: &Position, b: &Position) -> bool {
+        a.white == b.white
+            && a.black == b.black
+            && a.turn == b.turn
+            && a.castles == b.castles
+            && a.en_passant_capture() == b.en_passant_capture()
+    }
+}
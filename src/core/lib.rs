@@ -1,6 +1,9 @@
 pub mod board;
 pub mod fill;
+pub mod game;
 pub mod mov;
+pub mod perft;
+pub mod pgn;
 pub mod position;
 pub mod search;
 pub mod util;
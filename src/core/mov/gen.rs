@@ -1,7 +1,7 @@
 use std::num::Wrapping;
 
 use crate::{
-    board::{Bits, BoardIndex},
+    board::{Bits, Board, BoardIndex, Castles},
     fill::{
         king_fill,
         knight::{knight_fill, KnightFill},
@@ -10,7 +10,7 @@ use crate::{
         south_pawn_attacks,
     },
     mov::{list::MoveList, Move, MoveFlag},
-    position::{Color, Position},
+    position::{Color, Position, PositionPieces},
 };
 
 pub fn least_set_one_bit(board: Bits) -> Bits {
@@ -20,6 +20,18 @@ pub fn least_set_one_bit(board: Bits) -> Bits {
     (board as i64 & -(board as i64)) as u64
 }
 
+/// Which of the legal moves to generate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GenMode {
+    /// Captures, including en passant, and promotions.
+    Captures,
+    /// Every move that isn't a capture or a promotion, including castling.
+    Quiets,
+    /// Every legal move if the side to move is in check, and none otherwise.
+    Evasions,
+    All,
+}
+
 #[derive(Debug)]
 struct TargetBoards {
     north: Bits,
@@ -38,6 +50,8 @@ struct TargetBoards {
     wnw: Bits,
     nw: Bits,
     nnw: Bits,
+    /// The enemy pieces giving check to the king.
+    checkers: Bits,
 }
 
 impl TargetBoards {
@@ -62,7 +76,8 @@ impl TargetBoards {
     }
 }
 
-fn generate_white_legal_target_bitboards(pos: &Position) -> TargetBoards {
+/// Generates the target bitboards of white's legal moves of the given kind.
+fn generate_white_legal_target_bitboards(pos: &Position, mode: GenMode) -> TargetBoards {
     let occupancy: Bits = !pos.get_all_pieces();
 
     // generate black attacks
@@ -115,7 +130,7 @@ fn generate_white_legal_target_bitboards(pos: &Position) -> TargetBoards {
     attacks |= _attacks;
     _super_attacks = SlideFill::sw_attacks(pos.white.king, occupancy);
     let mut super_attacks_diag: Bits = _super_attacks;
-    let pdiag_between: Bits = _attacks & _super_attacks;
+    let mut pdiag_between: Bits = _attacks & _super_attacks;
 
     // south west
     _attacks = SlideFill::sw_attacks(
@@ -125,6 +140,7 @@ fn generate_white_legal_target_bitboards(pos: &Position) -> TargetBoards {
     attacks |= _attacks;
     _super_attacks = SlideFill::ne_attacks(pos.white.king, occupancy);
     super_attacks_diag |= _super_attacks;
+    pdiag_between |= _attacks & _super_attacks;
 
     // north west
     _attacks = SlideFill::nw_attacks(
@@ -136,7 +152,7 @@ fn generate_white_legal_target_bitboards(pos: &Position) -> TargetBoards {
     super_attacks_diag |= _super_attacks;
     let mut ndiag_between: Bits = _attacks & _super_attacks;
 
-    // south west
+    // south east
     _attacks = SlideFill::se_attacks(
         pos.black.queens | pos.black.bishops,
         occupancy ^ pos.white.king,
@@ -163,13 +179,24 @@ fn generate_white_legal_target_bitboards(pos: &Position) -> TargetBoards {
         | (knight_fill(pos.white.king) & pos.black.knights)
         | (north_pawn_attacks(pos.white.king) & pos.black.pawns);
 
-    let _null_if_check: Bits = (((attacks & pos.white.king) as i64 - 1) >> 63) as u64;
+    let _null_if_check: Bits = (((attacks & pos.white.king) as i64).wrapping_sub(1) >> 63) as u64;
     let _null_if_double_check: Bits =
-        (((_check_from & ((_check_from as i64) - 1) as u64) as i64 - 1) >> 63) as u64;
+        ((((_check_from & _check_from.wrapping_sub(1)) as i64).wrapping_sub(1)) >> 63) as u64;
 
     let _check_to = _check_from | _blocks | _null_if_check;
     let mut target_mask: Bits = !pos.white.get_all_pieces() & _check_to & _null_if_double_check;
 
+    // restrict the targets to the kind of move being generated: the squares pieces may move
+    // to, the squares pawns may push to, and whether en passant is allowed
+    let (mode_mask, push_mode_mask, en_passant_mode_mask): (Bits, Bits, Bits) = match mode {
+        GenMode::All => (!0, !0, !0),
+        GenMode::Captures => (pos.black.get_all_pieces(), 0xff00000000000000, !0),
+        GenMode::Quiets => (!pos.black.get_all_pieces(), !0xff00000000000000, 0),
+        GenMode::Evasions => (!_null_if_check, !_null_if_check, !_null_if_check),
+    };
+    let push_target_mask: Bits = target_mask & push_mode_mask;
+    target_mask &= mode_mask;
+
     // sliders
     let mut _sliders: Bits = (pos.white.rooks | pos.white.queens) & !(between ^ horizontal_between);
     let mut west_move_targets: Bits = SlideFill::west_attacks(_sliders, occupancy) & target_mask;
@@ -199,30 +226,41 @@ fn generate_white_legal_target_bitboards(pos: &Position) -> TargetBoards {
     let nnw_move_targets: Bits = KnightFill::nnw(available_knights) & target_mask;
 
     // pawn captures
-    let pawn_targets: Bits = (pos.black.get_all_pieces() & target_mask)
-        | if let Some(en_passant) = pos.en_passant {
-            (1_u64 << en_passant) & -((en_passant < 64) as i64) as u64
-        } else {
-            0
-        };
+    let pawn_targets: Bits = pos.black.get_all_pieces() & target_mask;
+    let en_passant: Bits = pos.en_passant.map_or(0, |index| 1_u64 << index) & en_passant_mode_mask;
+    let en_passant_check_to: Bits = _check_to & _null_if_double_check;
+
     let mut available_pawns: Bits = pos.white.pawns & !(between ^ pdiag_between);
     ne_move_targets |= SlideFill::ne_one(available_pawns) & pawn_targets;
+    ne_move_targets |= en_passant_target(
+        pos,
+        en_passant,
+        SlideFill::sw_one(en_passant) & available_pawns,
+        en_passant_check_to,
+    );
 
     available_pawns = pos.white.pawns & !(between ^ ndiag_between);
     nw_move_targets |= SlideFill::nw_one(available_pawns) & pawn_targets;
+    nw_move_targets |= en_passant_target(
+        pos,
+        en_passant,
+        SlideFill::se_one(en_passant) & available_pawns,
+        en_passant_check_to,
+    );
 
     // pawn pushes
-    // available_pawns = pos.white.pawns & !(between ^ vertical_between);
-    let pawn_pushes: Bits = SlideFill::north_one(pos.white.pawns) & occupancy;
-    north_move_targets |= pawn_pushes & target_mask;
+    available_pawns = pos.white.pawns & !(between ^ vertical_between);
+    let pawn_pushes: Bits = SlideFill::north_one(available_pawns) & occupancy;
+    north_move_targets |= pawn_pushes & push_target_mask;
 
     // double pawn pushes
     let double_pawn_pushes =
-        SlideFill::north_one(pawn_pushes) & occupancy & target_mask & 0x00000000ff000000;
+        SlideFill::north_one(pawn_pushes) & occupancy & push_target_mask & 0x00000000ff000000;
     north_move_targets |= double_pawn_pushes;
 
     // king moves
-    target_mask = !(pos.white.get_all_pieces() | attacks);
+    // castling is ruled out with the king's other moves, since it needs a quiet step first
+    target_mask = !(pos.white.get_all_pieces() | attacks) & mode_mask;
 
     north_move_targets |= SlideFill::north_one(pos.white.king) & target_mask;
     ne_move_targets |= SlideFill::ne_one(pos.white.king) & target_mask;
@@ -232,13 +270,18 @@ fn generate_white_legal_target_bitboards(pos: &Position) -> TargetBoards {
     nw_move_targets |= SlideFill::nw_one(pos.white.king) & target_mask;
 
     // my solution to king-side castling
+    // the king can only step on to the castle square from an empty, unattacked square
+    // and only with its rook still in the corner
+    let white_castles = pos.castles
+        & (Castles::WHITE_KING & pos.white.rooks >> 1
+            | Castles::WHITE_QUEEN & pos.white.rooks << 2);
     let mut east_king_moves = SlideFill::east_one(pos.white.king) & target_mask;
-    east_king_moves |= SlideFill::east_one(east_king_moves)
-        & (pos.castles & !attacks & _null_if_check & !pos.get_all_pieces());
+    east_king_moves |= SlideFill::east_one(east_king_moves & occupancy)
+        & (white_castles & !attacks & _null_if_check & !pos.get_all_pieces());
     let mut west_king_moves = SlideFill::west_one(pos.white.king) & target_mask;
     let west_castle_mask = !pos.get_all_pieces() & !(pos.get_all_pieces() << 1);
-    west_king_moves |= SlideFill::west_one(west_king_moves)
-        & (pos.castles & !attacks & _null_if_check)
+    west_king_moves |= SlideFill::west_one(west_king_moves & occupancy)
+        & (white_castles & !attacks & _null_if_check)
         & west_castle_mask;
 
     east_move_targets |= east_king_moves;
@@ -261,7 +304,87 @@ fn generate_white_legal_target_bitboards(pos: &Position) -> TargetBoards {
         wnw: wnw_move_targets,
         nw: nw_move_targets,
         nnw: nnw_move_targets,
+        checkers: _check_from,
+    }
+}
+
+/// Returns the en passant square if `capturer` (the pawn behind it, or nothing) may capture
+/// onto it. The capture must resolve any check, and it must not expose the king once both pawns
+/// have left their squares.
+fn en_passant_target(pos: &Position, en_passant: Bits, capturer: Bits, check_to: Bits) -> Bits {
+    if capturer == 0 {
+        return 0;
+    }
+
+    let captured: Bits = SlideFill::south_one(en_passant);
+    if (en_passant | captured) & check_to == 0 {
+        return 0;
+    }
+
+    if en_passant_exposes_king(&pos.white, &pos.black, en_passant, capturer) {
+        0
+    } else {
+        en_passant
+    }
+}
+
+/// Returns true if white's king is attacked once `capturer` has taken the pawn in front of
+/// `en_passant`. Both pawns leave their squares, so this catches the pins along their rank that
+/// the between masks miss, as well as any other attack on the king.
+fn en_passant_exposes_king(
+    white: &PositionPieces,
+    black: &PositionPieces,
+    en_passant: Bits,
+    capturer: Bits,
+) -> bool {
+    let captured: Bits = SlideFill::south_one(en_passant);
+    let open: Bits =
+        (!(white.get_all_pieces() | black.get_all_pieces()) | captured | capturer) & !en_passant;
+    let king: Bits = white.king;
+
+    let orthogonal: Bits = SlideFill::north_attacks(king, open)
+        | SlideFill::south_attacks(king, open)
+        | SlideFill::east_attacks(king, open)
+        | SlideFill::west_attacks(king, open);
+    let diagonal: Bits = SlideFill::ne_attacks(king, open)
+        | SlideFill::nw_attacks(king, open)
+        | SlideFill::se_attacks(king, open)
+        | SlideFill::sw_attacks(king, open);
+
+    (orthogonal & (black.rooks | black.queens))
+        | (diagonal & (black.bishops | black.queens))
+        | (knight_fill(king) & black.knights)
+        | (north_pawn_attacks(king) & black.pawns & !captured)
+        != 0
+}
+
+/// Returns true if the side to move can capture en passant. Only the pawns beside the one that
+/// just moved are tried, which is much cheaper than generating every legal move.
+pub fn can_capture_en_passant(pos: &Position) -> bool {
+    let Some(square) = pos.en_passant else {
+        return false;
+    };
+
+    // look at the board from the side to move, as the generator does
+    let (white, black, square) = match pos.turn {
+        Color::White => (pos.white.clone(), pos.black.clone(), square),
+        Color::Black => (
+            pos.black.mirror(),
+            pos.white.mirror(),
+            Board::mirror_index(square),
+        ),
+    };
+    let en_passant: Bits = 1 << square;
+
+    let mut capturers: Bits = south_pawn_attacks(en_passant) & white.pawns;
+    while capturers != 0 {
+        let capturer = least_set_one_bit(capturers);
+        if !en_passant_exposes_king(&white, &black, en_passant, capturer) {
+            return true;
+        }
+        capturers ^= capturer;
     }
+    false
 }
 
 const MAGIC_ARRAY: [BoardIndex; 64] = [
@@ -276,7 +399,32 @@ fn bitscan(board: Bits) -> BoardIndex {
     MAGIC_ARRAY[((Wrapping(least_set_one_bit(board)) * Wrapping(DE_BRUIJN_SEQ)).0 >> 58) as usize]
 }
 
-pub fn generate_legal_moves(_pos: &Position) -> MoveList {
+/// Returns a bitboard of the pieces giving check to the side to move.
+pub fn get_checkers(pos: &Position) -> Bits {
+    match pos.turn {
+        Color::White => generate_white_legal_target_bitboards(pos, GenMode::All).checkers,
+        Color::Black => Board::mirror_board(
+            generate_white_legal_target_bitboards(&pos.mirror(), GenMode::All).checkers,
+        ),
+    }
+}
+
+/// Returns true if the side to move is in check.
+pub fn is_in_check(pos: &Position) -> bool {
+    get_checkers(pos) != 0
+}
+
+pub fn generate_legal_moves(pos: &Position) -> MoveList {
+    generate_moves(pos, GenMode::All)
+}
+
+/// Generates the legal captures, including en passant, and promotions.
+pub fn generate_captures(pos: &Position) -> MoveList {
+    generate_moves(pos, GenMode::Captures)
+}
+
+/// Generates the legal moves of the given kind.
+pub fn generate_moves(_pos: &Position, mode: GenMode) -> MoveList {
     let pos = match _pos.turn {
         Color::White => _pos.clone(),
         Color::Black => {
@@ -289,7 +437,7 @@ pub fn generate_legal_moves(_pos: &Position) -> MoveList {
     let mut move_list = MoveList::default();
 
     // generate move target bitboards
-    let move_target_bitboards = generate_white_legal_target_bitboards(&pos);
+    let move_target_bitboards = generate_white_legal_target_bitboards(&pos, mode);
     let all_pieces: Bits = pos.get_all_pieces();
 
     // north
@@ -574,7 +722,7 @@ pub fn generate_legal_moves(_pos: &Position) -> MoveList {
             // if moving piece is a king
             let diff: i8 = to as i8 - from as i8;
 
-            if diff == -3 {
+            if diff == -2 {
                 move_list.append(Move::new(to, from, MoveFlag::QUEEN_CASTLE));
             } else {
                 move_list.append(Move::new(to, from, capture_flag));
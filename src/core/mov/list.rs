@@ -1,3 +1,5 @@
+use std::fmt;
+
 use crate::mov::Move;
 
 #[derive(Clone, Debug)]
@@ -53,13 +55,12 @@ impl MoveList {
     }
 }
 
-impl ToString for MoveList {
-    fn to_string(&self) -> String {
-        let mut out = String::new();
+impl fmt::Display for MoveList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         for mov in self.clone().into_iter() {
-            out += &(mov.to_string() + "\n");
+            writeln!(f, "{}", mov)?;
         }
-        out
+        Ok(())
     }
 }
 
@@ -1,10 +1,11 @@
-use crate::{
-    board::Board,
-    position::{index_to_alg, Piece},
-};
+use std::fmt;
+
+use crate::{board::Board, position::Piece};
 
 pub mod gen;
 pub mod list;
+pub mod san;
+pub mod uci;
 
 pub struct MoveFlag;
 impl MoveFlag {
@@ -62,28 +63,38 @@ impl Move {
 
     #[inline]
     pub fn is_capture(&self) -> bool {
-        return self.get_flags() & MoveFlag::CAPTURE != 0;
+        self.get_flags() & MoveFlag::CAPTURE != 0
     }
 
     #[inline]
     pub fn is_promotion(&self) -> bool {
-        return self.get_flags() & MoveFlag::PROMOTION != 0;
+        self.get_flags() & MoveFlag::PROMOTION != 0
     }
 
     pub fn is_double_pawn_push(self: Move) -> bool {
-        let flags = self.get_flags();
-        return !(flags & MoveFlag::PROMOTION != 0)
-            && !(flags & MoveFlag::CAPTURE != 0)
-            && (flags & MoveFlag::DOUBLE_PAWN_PUSH != 0);
+        self.get_flags() == MoveFlag::DOUBLE_PAWN_PUSH
+    }
+
+    pub fn is_king_castle(self: Move) -> bool {
+        self.get_flags() == MoveFlag::KING_CASTLE
+    }
+
+    pub fn is_queen_castle(self: Move) -> bool {
+        self.get_flags() == MoveFlag::QUEEN_CASTLE
+    }
+
+    pub fn is_castle(self: Move) -> bool {
+        self.is_king_castle() || self.is_queen_castle()
     }
 
     pub fn is_en_passant(self: Move) -> bool {
         let flags = self.get_flags();
-        return !self.is_promotion() && self.is_capture() && flags & MoveFlag::EN_PASSANT != 0;
+        !self.is_promotion() && self.is_capture() && flags & MoveFlag::EN_PASSANT != 0
     }
 
-    pub fn get_butterfly_index(&self) -> u8 {
-        (self.bits & 0xfff) as u8
+    /// Returns the from and to squares as one index below 4096, for tables indexed by both.
+    pub fn get_butterfly_index(&self) -> u16 {
+        self.bits & 0xfff
     }
 
     pub fn flip(mov: &Move) -> Move {
@@ -104,29 +115,21 @@ impl Move {
             return None;
         }
 
-        // important that queen is checked first, because Q = R | B
-        if self.bits & ((MoveFlag::QUEEN_PROMOTION as u16) << 12) != 0 {
-            Some(Piece::Queen)
-        } else if self.bits & ((MoveFlag::ROOK_PROMOTION as u16) << 12) != 0 {
-            Some(Piece::Rook)
-        } else if self.bits & ((MoveFlag::BISHOP_PROMOTION as u16) << 12) != 0 {
-            Some(Piece::Bishop)
-        } else if self.bits & ((MoveFlag::KNIGHT_PROMOTION as u16) << 12) != 0 {
-            Some(Piece::Knight)
-        } else {
-            None
+        // the low two flag bits select the piece
+        match self.get_flags() & 0x3 {
+            MoveFlag::KNIGHT_PROMOTION => Some(Piece::Knight),
+            MoveFlag::BISHOP_PROMOTION => Some(Piece::Bishop),
+            MoveFlag::ROOK_PROMOTION => Some(Piece::Rook),
+            _ => Some(Piece::Queen),
         }
     }
 }
 
-impl ToString for Move {
-    fn to_string(&self) -> String {
-        // todo: make this algebraic notation
-        format!(
-            "{} -> {}",
-            index_to_alg(self.get_from()),
-            index_to_alg(self.get_to())
-        )
+/// Moves are displayed in UCI long algebraic notation, since SAN needs the position.
+/// See `Position::move_to_san`.
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_uci())
     }
 }
 
@@ -135,3 +138,5 @@ impl PartialEq for Move {
         other.bits == self.bits
     }
 }
+
+impl Eq for Move {}
@@ -0,0 +1,224 @@
+use crate::{
+    mov::{
+        gen::{generate_legal_moves, is_in_check},
+        Move,
+    },
+    position::{alg_to_index, index_to_alg, Piece, Position},
+};
+
+fn piece_letter(piece: Piece) -> Option<char> {
+    match piece {
+        Piece::Pawn => None,
+        Piece::Knight => Some('N'),
+        Piece::Bishop => Some('B'),
+        Piece::Rook => Some('R'),
+        Piece::Queen => Some('Q'),
+        Piece::King => Some('K'),
+    }
+}
+
+fn letter_piece(c: char) -> Option<Piece> {
+    match c {
+        'P' => Some(Piece::Pawn),
+        'N' => Some(Piece::Knight),
+        'B' => Some(Piece::Bishop),
+        'R' => Some(Piece::Rook),
+        'Q' => Some(Piece::Queen),
+        'K' => Some(Piece::King),
+        _ => None,
+    }
+}
+
+impl Position {
+    /// Formats a legal move in Standard Algebraic Notation, e.g. `Nbd7`, `exd5`, `e8=Q+` or
+    /// `O-O`.
+    pub fn move_to_san(&self, mov: &Move) -> String {
+        let mut out = String::new();
+
+        if mov.is_king_castle() {
+            out.push_str("O-O");
+        } else if mov.is_queen_castle() {
+            out.push_str("O-O-O");
+        } else {
+            let piece = self
+                .get_square(mov.get_from())
+                .expect("tried to format a move from an empty square")
+                .1;
+            let from = index_to_alg(mov.get_from());
+
+            match piece_letter(piece) {
+                Some(letter) => {
+                    out.push(letter);
+                    out.push_str(&self.disambiguation(mov, piece));
+                }
+                // pawn captures are identified by their file
+                None if mov.is_capture() => out.push_str(&from[0..1]),
+                None => {}
+            }
+
+            if mov.is_capture() {
+                out.push('x');
+            }
+
+            out.push_str(&index_to_alg(mov.get_to()));
+
+            if let Some(promotion) = mov.get_promotion_piece() {
+                out.push('=');
+                out.push(piece_letter(promotion).unwrap());
+            }
+        }
+
+        let (next, _) = self.apply_move(mov);
+        if is_in_check(&next) {
+            out.push(if generate_legal_moves(&next).count() == 0 {
+                '#'
+            } else {
+                '+'
+            });
+        }
+
+        out
+    }
+
+    /// Returns the from-square prefix needed to tell `mov` apart from other moves of the same
+    /// piece type to the same square: the file if it's unique, else the rank, else both.
+    fn disambiguation(&self, mov: &Move, piece: Piece) -> String {
+        let others: Vec<Move> = generate_legal_moves(self)
+            .into_iter()
+            .filter(|other| {
+                other.get_to() == mov.get_to()
+                    && other.get_from() != mov.get_from()
+                    && self.get_square(other.get_from()).map(|(_, p)| p) == Some(piece)
+            })
+            .collect();
+
+        let from = index_to_alg(mov.get_from());
+        if others.is_empty() {
+            String::new()
+        } else if others
+            .iter()
+            .all(|o| o.get_from() % 8 != mov.get_from() % 8)
+        {
+            from[0..1].to_string()
+        } else if others
+            .iter()
+            .all(|o| o.get_from() / 8 != mov.get_from() / 8)
+        {
+            from[1..2].to_string()
+        } else {
+            from
+        }
+    }
+
+    /// Parses a move in Standard Algebraic Notation and resolves it against the legal moves in
+    /// this position.
+    /// The parser is tolerant: check and annotation suffixes (`+`, `#`, `!`, `?`), capture
+    /// markers, `=` before promotions, zeros in castles and long algebraic hyphens are all
+    /// optional, and over-specified from-squares are accepted.
+    pub fn parse_san_move(&self, s: &str) -> Result<Move, String> {
+        let trimmed = s
+            .trim()
+            .trim_end_matches(['+', '#', '!', '?'])
+            .trim_end_matches("e.p.")
+            .trim_end();
+
+        let legal = generate_legal_moves(self);
+
+        let castle = match trimmed {
+            "O-O" | "0-0" => Some(true),
+            "O-O-O" | "0-0-0" => Some(false),
+            _ => None,
+        };
+        if let Some(king_side) = castle {
+            return legal
+                .into_iter()
+                .find(|mov| {
+                    if king_side {
+                        mov.is_king_castle()
+                    } else {
+                        mov.is_queen_castle()
+                    }
+                })
+                .ok_or(format!(
+                    "illegal move '{}': can't castle in {}",
+                    s,
+                    self.to_fen()
+                ));
+        }
+
+        let mut chars: Vec<char> = trimmed
+            .chars()
+            .filter(|c| !matches!(c, 'x' | ':' | '-' | '='))
+            .collect();
+
+        if !chars.iter().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(format!("invalid SAN move '{}'", s));
+        }
+
+        // the promotion piece comes last, after the target square
+        let promotion = match chars.last() {
+            Some(&c) if !c.is_ascii_digit() => {
+                chars.pop();
+                match letter_piece(c.to_ascii_uppercase()) {
+                    Some(Piece::Pawn) | Some(Piece::King) | None => {
+                        return Err(format!("invalid promotion piece '{}' in '{}'", c, s))
+                    }
+                    promotion => promotion,
+                }
+            }
+            _ => None,
+        };
+
+        if chars.len() < 2 {
+            return Err(format!("invalid SAN move '{}'", s));
+        }
+        let target: String = chars.split_off(chars.len() - 2).into_iter().collect();
+        let to = alg_to_index(&target).map_err(|e| format!("invalid SAN move '{}': {}", s, e))?;
+
+        // what's left is the piece letter (if it isn't a pawn) and the disambiguation
+        let piece = match chars.first().and_then(|&c| letter_piece(c)) {
+            Some(piece) => {
+                chars.remove(0);
+                piece
+            }
+            None => Piece::Pawn,
+        };
+
+        let mut from_file: Option<u8> = None;
+        let mut from_rank: Option<u8> = None;
+        for c in chars {
+            match c {
+                'a'..='h' if from_file.is_none() => from_file = Some(c as u8 - b'a'),
+                '1'..='8' if from_rank.is_none() => from_rank = Some(c as u8 - b'1'),
+                _ => return Err(format!("invalid SAN move '{}'", s)),
+            }
+        }
+
+        let candidates: Vec<Move> = legal
+            .into_iter()
+            .filter(|mov| {
+                mov.get_to() == to
+                    && !mov.is_castle()
+                    && self.get_square(mov.get_from()).map(|(_, p)| p) == Some(piece)
+                    && from_file.is_none_or(|file| mov.get_from() % 8 == file)
+                    && from_rank.is_none_or(|rank| mov.get_from() / 8 == rank)
+                    && mov.get_promotion_piece() == promotion
+            })
+            .collect();
+
+        match candidates.len() {
+            1 => Ok(candidates[0]),
+            0 => Err(format!("illegal move '{}' in {}", s, self.to_fen())),
+            _ => Err(format!(
+                "ambiguous move '{}' in {}: could be {}",
+                s,
+                self.to_fen(),
+                candidates
+                    .iter()
+                    .map(|mov| self.move_to_san(mov))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )),
+        }
+    }
+}
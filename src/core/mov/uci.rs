@@ -0,0 +1,52 @@
+use crate::{
+    mov::{gen::generate_legal_moves, Move},
+    position::{alg_to_index, index_to_alg, Piece, Position},
+};
+
+impl Move {
+    /// Formats this move in UCI long algebraic notation, e.g. `e2e4`, `e7e8q` or `e1g1`.
+    pub fn to_uci(&self) -> String {
+        let mut out = index_to_alg(self.get_from()) + &index_to_alg(self.get_to());
+
+        match self.get_promotion_piece() {
+            Some(Piece::Knight) => out.push('n'),
+            Some(Piece::Bishop) => out.push('b'),
+            Some(Piece::Rook) => out.push('r'),
+            Some(Piece::Queen) => out.push('q'),
+            _ => {}
+        }
+
+        out
+    }
+}
+
+impl Position {
+    /// Parses a move in UCI long algebraic notation and resolves it against the legal moves in
+    /// this position, so the returned move carries the right capture, castle, en passant and
+    /// promotion flags.
+    pub fn parse_uci_move(&self, s: &str) -> Result<Move, String> {
+        if !s.is_ascii() || (s.len() != 4 && s.len() != 5) {
+            return Err(format!("invalid uci move '{}'", s));
+        }
+
+        let from = alg_to_index(&s[0..2])?;
+        let to = alg_to_index(&s[2..4])?;
+        let promotion = match s[4..].chars().next() {
+            None => None,
+            Some('n') => Some(Piece::Knight),
+            Some('b') => Some(Piece::Bishop),
+            Some('r') => Some(Piece::Rook),
+            Some('q') => Some(Piece::Queen),
+            Some(c) => return Err(format!("invalid promotion piece '{}' in '{}'", c, s)),
+        };
+
+        generate_legal_moves(self)
+            .into_iter()
+            .find(|mov| {
+                mov.get_from() == from
+                    && mov.get_to() == to
+                    && mov.get_promotion_piece() == promotion
+            })
+            .ok_or(format!("{} is not a legal move in {}", s, self.to_fen()))
+    }
+}
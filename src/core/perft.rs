@@ -0,0 +1,53 @@
+use crate::{mov::gen::generate_legal_moves, position::Position};
+
+/// Counts the leaf nodes of the legal move tree to the given depth.
+/// Comparing against known counts is the standard way to verify a move generator.
+pub fn perft(pos: &Position, depth: u8) -> u64 {
+    let mut pos = pos.clone();
+    perft_recursive(&mut pos, depth)
+}
+
+fn perft_recursive(pos: &mut Position, depth: u8) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let moves = generate_legal_moves(pos);
+
+    // bulk count the last ply
+    if depth == 1 {
+        return moves.count() as u64;
+    }
+
+    let mut nodes = 0;
+    for mov in moves.into_iter() {
+        let undo = pos.make_move(mov);
+        nodes += perft_recursive(pos, depth - 1);
+        pos.unmake_move(mov, undo);
+    }
+
+    nodes
+}
+
+/// Runs perft below each legal move, printing the node count of every move and the total.
+/// Returns the total number of nodes.
+pub fn divide(pos: &Position, depth: u8) -> u64 {
+    let mut pos = pos.clone();
+    let mut total = 0;
+
+    for mov in generate_legal_moves(&pos).into_iter() {
+        let undo = pos.make_move(mov);
+        let nodes = if depth > 1 {
+            perft_recursive(&mut pos, depth - 1)
+        } else {
+            1
+        };
+        pos.unmake_move(mov, undo);
+
+        println!("{}: {}", mov, nodes);
+        total += nodes;
+    }
+
+    println!("\nnodes: {}", total);
+    total
+}
@@ -0,0 +1,136 @@
+use crate::{game::Game, mov::Move, position::Position};
+
+pub mod read;
+pub mod write;
+
+pub use self::read::PgnReader;
+
+/// The tags every PGN game should have, in the order they are exported.
+pub const SEVEN_TAG_ROSTER: [&str; 7] =
+    ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+/// A move in a PGN game tree, along with its annotations and the moves that follow it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PgnNode {
+    pub mov: Move,
+    /// Numeric annotation glyphs, e.g. 1 for `!` or 4 for `??`.
+    pub nags: Vec<u8>,
+    /// A comment before the move. Only used for the first move of a variation.
+    pub starting_comment: Option<String>,
+    /// A comment after the move.
+    pub comment: Option<String>,
+    /// The moves that can follow this one. The first is the main line, the rest are variations.
+    pub children: Vec<PgnNode>,
+}
+
+impl PgnNode {
+    pub fn new(mov: Move) -> PgnNode {
+        PgnNode {
+            mov,
+            nags: Vec::new(),
+            starting_comment: None,
+            comment: None,
+            children: Vec::new(),
+        }
+    }
+}
+
+/// A game in PGN: its tag pairs and a tree of moves rooted at the starting position.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PgnGame {
+    /// Tag pairs in the order they were read or added.
+    pub tags: Vec<(String, String)>,
+    /// The starting position, from the `FEN` tag or the standard position.
+    pub start: Position,
+    /// A comment before the first move.
+    pub comment: Option<String>,
+    /// The first moves of the game. The first is the main line, the rest are variations.
+    pub children: Vec<PgnNode>,
+}
+
+impl PgnGame {
+    /// Returns an empty game from the standard starting position.
+    pub fn new() -> PgnGame {
+        PgnGame::from_position(
+            Position::from_fen(Position::STANDARD_FEN.into()).expect("standard fen is valid"),
+        )
+    }
+
+    /// Returns an empty game from the given position, setting the `FEN` and `SetUp` tags if it
+    /// isn't the standard starting position.
+    pub fn from_position(start: Position) -> PgnGame {
+        let mut game = PgnGame {
+            tags: Vec::new(),
+            start,
+            comment: None,
+            children: Vec::new(),
+        };
+
+        if game.start.to_fen() != Position::STANDARD_FEN {
+            game.set_tag("SetUp", "1");
+            game.set_tag("FEN", &game.start.to_fen());
+        }
+
+        game
+    }
+
+    /// Builds a game with no variations from the moves of a `Game`.
+    pub fn from_game(game: &Game) -> PgnGame {
+        let mut pgn = PgnGame::from_position(game.start().clone());
+
+        let mut children = &mut pgn.children;
+        for mov in game.moves() {
+            children.push(PgnNode::new(*mov));
+            children = &mut children[0].children;
+        }
+
+        pgn
+    }
+
+    /// Returns the value of a tag, if it is set.
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Sets a tag, replacing its value if it is already set.
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some((_, old)) => *old = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    /// Returns the result tag, or `*` if the result is unknown.
+    pub fn result(&self) -> &str {
+        self.tag("Result").unwrap_or("*")
+    }
+
+    /// Returns the moves of the main line.
+    pub fn mainline(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        let mut children = &self.children;
+        while let Some(node) = children.first() {
+            moves.push(node.mov);
+            children = &node.children;
+        }
+        moves
+    }
+
+    /// Replays the main line into a `Game`.
+    pub fn to_game(&self) -> Result<Game, String> {
+        let mut game = Game::new(self.start.clone());
+        for mov in self.mainline() {
+            game.play(mov)?;
+        }
+        Ok(game)
+    }
+}
+
+impl Default for PgnGame {
+    fn default() -> Self {
+        PgnGame::new()
+    }
+}
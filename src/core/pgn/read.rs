@@ -0,0 +1,393 @@
+use std::io::BufRead;
+
+use crate::position::Position;
+
+use super::{PgnGame, PgnNode};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Tag(String, String),
+    MoveNumber,
+    San(String),
+    Nag(u8),
+    Comment(String),
+    VariationStart,
+    VariationEnd,
+    Result(String),
+}
+
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+/// Returns the NAG for a move suffix annotation like `!?`.
+fn suffix_nag(suffix: &str) -> Option<u8> {
+    match suffix {
+        "!" => Some(1),
+        "?" => Some(2),
+        "!!" => Some(3),
+        "??" => Some(4),
+        "!?" => Some(5),
+        "?!" => Some(6),
+        _ => None,
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    let mut line_start = true;
+
+    while let Some(c) = chars.next() {
+        match c {
+            // a % at the start of a line escapes the rest of it
+            '%' if line_start => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '{' => {
+                let mut comment = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => comment.push(c),
+                        None => return Err("unterminated comment".to_string()),
+                    }
+                }
+                tokens.push(Token::Comment(comment.trim().to_string()));
+            }
+            ';' => {
+                let mut comment = String::new();
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                    comment.push(c);
+                }
+                tokens.push(Token::Comment(comment.trim().to_string()));
+            }
+            '[' => {
+                let mut tag = String::new();
+                loop {
+                    match chars.next() {
+                        Some(']') => break,
+                        Some('"') => {
+                            tag.push('"');
+                            // copy the quoted value, keeping escapes for parse_tag
+                            loop {
+                                match chars.next() {
+                                    Some('"') => break,
+                                    Some('\\') => {
+                                        tag.push('\\');
+                                        match chars.next() {
+                                            Some(c) => tag.push(c),
+                                            None => break,
+                                        }
+                                    }
+                                    Some(c) => tag.push(c),
+                                    None => return Err("unterminated tag value".to_string()),
+                                }
+                            }
+                            tag.push('"');
+                        }
+                        Some(c) => tag.push(c),
+                        None => return Err("unterminated tag".to_string()),
+                    }
+                }
+                let (name, value) = parse_tag(&tag)?;
+                tokens.push(Token::Tag(name, value));
+            }
+            '(' => tokens.push(Token::VariationStart),
+            ')' => tokens.push(Token::VariationEnd),
+            '$' => {
+                let mut nag = String::new();
+                while let Some(c) = chars.peek().filter(|c| c.is_ascii_digit()) {
+                    nag.push(*c);
+                    chars.next();
+                }
+                let nag = nag.parse().map_err(|_| format!("invalid NAG: ${}", nag))?;
+                tokens.push(Token::Nag(nag));
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                let mut symbol = c.to_string();
+                while let Some(c) = chars
+                    .peek()
+                    .filter(|c| !c.is_whitespace() && !"{}()[];$".contains(**c))
+                {
+                    symbol.push(*c);
+                    chars.next();
+                }
+                push_symbol(&mut tokens, &symbol)?;
+            }
+        }
+
+        line_start = c == '\n';
+    }
+
+    Ok(tokens)
+}
+
+/// Parses the inside of a tag pair like `Event "Casual game"`.
+fn parse_tag(tag: &str) -> Result<(String, String), String> {
+    let tag = tag.trim();
+    let (name, quoted) = tag
+        .split_once(char::is_whitespace)
+        .ok_or_else(|| format!("invalid tag: [{}]", tag))?;
+    let quoted = quoted.trim();
+
+    if name.is_empty()
+        || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        || quoted.len() < 2
+        || !quoted.starts_with('"')
+        || !quoted.ends_with('"')
+    {
+        return Err(format!("invalid tag: [{}]", tag));
+    }
+
+    let mut value = String::new();
+    let mut chars = quoted[1..quoted.len() - 1].chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => value.extend(chars.next()),
+            c => value.push(c),
+        }
+    }
+
+    Ok((name.to_string(), value))
+}
+
+/// Turns a run of symbol characters into tokens: a result, a move number and/or a SAN move
+/// with its suffix annotation.
+fn push_symbol(tokens: &mut Vec<Token>, symbol: &str) -> Result<(), String> {
+    if RESULTS.contains(&symbol) {
+        tokens.push(Token::Result(symbol.to_string()));
+        return Ok(());
+    }
+
+    let mut san = symbol;
+    let digits = san.trim_start_matches(|c: char| c.is_ascii_digit());
+    if digits.len() < san.len() && digits.starts_with('.') {
+        tokens.push(Token::MoveNumber);
+        san = digits.trim_start_matches('.');
+        if san.is_empty() {
+            return Ok(());
+        }
+    }
+
+    let stripped = san.trim_end_matches(['!', '?']);
+    if stripped.is_empty() {
+        return Err(format!("invalid symbol: {}", symbol));
+    }
+    tokens.push(Token::San(stripped.to_string()));
+
+    let suffix = &san[stripped.len()..];
+    if !suffix.is_empty() {
+        let nag = suffix_nag(suffix).ok_or_else(|| format!("invalid annotation: {}", symbol))?;
+        tokens.push(Token::Nag(nag));
+    }
+
+    Ok(())
+}
+
+fn append_comment(target: &mut Option<String>, comment: String) {
+    match target {
+        Some(existing) => {
+            existing.push(' ');
+            existing.push_str(&comment);
+        }
+        None => *target = Some(comment),
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    index: usize,
+    result: Option<String>,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.index).cloned();
+        self.index += 1;
+        token
+    }
+
+    /// Parses a line of moves from `pos` up to the end of the variation or game.
+    /// Returns the comment before the first move and the alternatives for the first move,
+    /// each with its continuation attached.
+    fn parse_line(
+        &mut self,
+        pos: &Position,
+        nested: bool,
+    ) -> Result<(Option<String>, Vec<PgnNode>), String> {
+        let mut leading = None;
+        // each move with its variations and the position it was played from
+        let mut line: Vec<(PgnNode, Vec<PgnNode>, Position)> = Vec::new();
+        let mut current = pos.clone();
+
+        loop {
+            match self.next() {
+                None if nested => return Err("unterminated variation".to_string()),
+                None => break,
+                Some(Token::Tag(name, _)) => {
+                    return Err(format!("unexpected tag in movetext: {}", name))
+                }
+                Some(Token::Comment(comment)) => match line.last_mut() {
+                    Some((node, _, _)) => append_comment(&mut node.comment, comment),
+                    None => append_comment(&mut leading, comment),
+                },
+                Some(Token::Nag(nag)) => match line.last_mut() {
+                    Some((node, _, _)) => node.nags.push(nag),
+                    None => return Err(format!("NAG ${} before any move", nag)),
+                },
+                Some(Token::MoveNumber) => {}
+                Some(Token::San(san)) => {
+                    let mov = current
+                        .parse_san_move(&san)
+                        .map_err(|e| format!("{} in {}", e, current.to_fen()))?;
+                    let (next, _) = current.apply_move(&mov);
+                    line.push((PgnNode::new(mov), Vec::new(), current));
+                    current = next;
+                }
+                Some(Token::VariationStart) => {
+                    let (_, variations, from) = line
+                        .last_mut()
+                        .ok_or_else(|| "variation before any move".to_string())?;
+                    let (comment, mut alternatives) = self.parse_line(from, true)?;
+                    if let Some(first) = alternatives.first_mut() {
+                        first.starting_comment = comment;
+                    }
+                    variations.append(&mut alternatives);
+                }
+                Some(Token::VariationEnd) if nested => break,
+                Some(Token::VariationEnd) => return Err("unexpected ')'".to_string()),
+                Some(Token::Result(result)) if !nested => {
+                    self.result = Some(result);
+                    break;
+                }
+                Some(Token::Result(result)) => {
+                    return Err(format!("unexpected result in variation: {}", result))
+                }
+            }
+        }
+
+        // a move's variations are its siblings, so they join it in its parent's children
+        let mut children = Vec::new();
+        for (mut node, variations, _) in line.into_iter().rev() {
+            node.children = children;
+            children = std::iter::once(node).chain(variations).collect();
+        }
+
+        Ok((leading, children))
+    }
+}
+
+impl PgnGame {
+    /// Parses a single game from PGN text. Use `PgnReader` for files with several games.
+    pub fn parse(text: &str) -> Result<PgnGame, String> {
+        let mut parser = Parser {
+            tokens: tokenize(text)?,
+            index: 0,
+            result: None,
+        };
+
+        let mut tags = Vec::new();
+        while let Some(Token::Tag(name, value)) = parser.tokens.get(parser.index) {
+            tags.push((name.clone(), value.clone()));
+            parser.index += 1;
+        }
+
+        let mut game = PgnGame::new();
+        game.tags = tags;
+
+        if let Some(fen) = game.tag("FEN") {
+            if game.tag("SetUp") != Some("0") {
+                game.start = Position::from_fen(fen.to_string())?;
+            }
+        }
+
+        let (comment, children) = parser.parse_line(&game.start.clone(), false)?;
+        game.comment = comment;
+        game.children = children;
+
+        if let Some(token) = parser.next() {
+            return Err(format!("unexpected {:?} after the game result", token));
+        }
+
+        if let Some(result) = parser.result {
+            if game.tag("Result").is_none() {
+                game.set_tag("Result", &result);
+            }
+        }
+
+        Ok(game)
+    }
+}
+
+/// Reads games one at a time from a PGN file with any number of them.
+pub struct PgnReader<R: BufRead> {
+    lines: std::io::Lines<R>,
+    /// The first line of the next game, read while looking for the end of the previous one.
+    pending: Option<String>,
+}
+
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(reader: R) -> PgnReader<R> {
+        PgnReader {
+            lines: reader.lines(),
+            pending: None,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<PgnGame, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut text = String::new();
+        let mut seen_movetext = false;
+        let mut in_comment = false;
+
+        let mut next_line = self.pending.take().map(Ok);
+        while let Some(line) = next_line.or_else(|| self.lines.next()) {
+            next_line = None;
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e.to_string())),
+            };
+            let trimmed = line.trim_start();
+
+            // a tag after the movetext starts the next game
+            if !in_comment && trimmed.starts_with('[') {
+                if seen_movetext {
+                    self.pending = Some(line);
+                    break;
+                }
+            } else if !in_comment && !trimmed.is_empty() && !line.starts_with('%') {
+                seen_movetext = true;
+            }
+
+            if in_comment || !trimmed.starts_with('[') && !line.starts_with('%') {
+                for c in line.chars() {
+                    match c {
+                        '{' if !in_comment => in_comment = true,
+                        '}' if in_comment => in_comment = false,
+                        ';' if !in_comment => break,
+                        _ => {}
+                    }
+                }
+            }
+
+            text.push_str(&line);
+            text.push('\n');
+        }
+
+        if text.trim().is_empty() {
+            None
+        } else {
+            Some(PgnGame::parse(&text))
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use std::fmt;
+
+use crate::position::{Color, Position};
+
+use super::{PgnGame, PgnNode, SEVEN_TAG_ROSTER};
+
+/// The longest line written, as recommended by the PGN standard.
+const LINE_LENGTH: usize = 80;
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Pushes a comment as one token per word, so that long comments can be wrapped.
+fn push_comment(tokens: &mut Vec<String>, comment: &str) {
+    // a comment can't contain its own closing brace
+    let comment = comment.replace('}', "");
+    let words: Vec<&str> = comment.split_whitespace().collect();
+    match words.len() {
+        0 => tokens.push("{}".to_string()),
+        1 => tokens.push(format!("{{{}}}", words[0])),
+        n => {
+            tokens.push(format!("{{{}", words[0]));
+            tokens.extend(words[1..n - 1].iter().map(|word| word.to_string()));
+            tokens.push(format!("{}}}", words[n - 1]));
+        }
+    }
+}
+
+/// Pushes the tokens for `nodes[0]` and its continuation, with `nodes[1..]` as variations.
+/// `numbered` forces a move number before a black move, as after a comment or variation.
+fn push_line(tokens: &mut Vec<String>, pos: &Position, nodes: &[PgnNode], mut numbered: bool) {
+    let Some(main) = nodes.first() else {
+        return;
+    };
+
+    if let Some(comment) = &main.starting_comment {
+        push_comment(tokens, comment);
+        numbered = true;
+    }
+
+    match pos.turn {
+        Color::White => tokens.push(format!("{}.", pos.fullmove_number)),
+        Color::Black if numbered => tokens.push(format!("{}...", pos.fullmove_number)),
+        Color::Black => {}
+    }
+    tokens.push(pos.move_to_san(&main.mov));
+    tokens.extend(main.nags.iter().map(|nag| format!("${}", nag)));
+
+    let mut interrupted = false;
+    if let Some(comment) = &main.comment {
+        push_comment(tokens, comment);
+        interrupted = true;
+    }
+
+    for variation in &nodes[1..] {
+        let start = tokens.len();
+        push_line(tokens, pos, std::slice::from_ref(variation), true);
+        tokens[start].insert(0, '(');
+        tokens.last_mut().expect("a variation has a move").push(')');
+        interrupted = true;
+    }
+
+    let (next, _) = pos.apply_move(&main.mov);
+    push_line(tokens, &next, &main.children, interrupted);
+}
+
+impl PgnGame {
+    /// Returns the game as PGN, with the seven tag roster first and the movetext wrapped to
+    /// 80 columns.
+    pub fn to_pgn(&self) -> String {
+        let mut pgn = String::new();
+
+        for name in SEVEN_TAG_ROSTER {
+            let value = self.tag(name).unwrap_or(match name {
+                "Date" => "????.??.??",
+                "Result" => "*",
+                _ => "?",
+            });
+            pgn.push_str(&format!("[{} \"{}\"]\n", name, escape(value)));
+        }
+        for (name, value) in &self.tags {
+            if !SEVEN_TAG_ROSTER.contains(&name.as_str()) {
+                pgn.push_str(&format!("[{} \"{}\"]\n", name, escape(value)));
+            }
+        }
+        pgn.push('\n');
+
+        let mut tokens = Vec::new();
+        if let Some(comment) = &self.comment {
+            push_comment(&mut tokens, comment);
+        }
+        push_line(&mut tokens, &self.start, &self.children, true);
+        tokens.push(self.result().to_string());
+
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > LINE_LENGTH {
+                pgn.push_str(&line);
+                pgn.push('\n');
+                line.clear();
+            } else if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        pgn.push_str(&line);
+        pgn.push('\n');
+
+        pgn
+    }
+}
+
+impl fmt::Display for PgnGame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_pgn())
+    }
+}
@@ -13,6 +13,8 @@ impl Position {
         let mut stage = 0;
         let mut skip_offset: i32 = 0;
         let mut ep_file: Option<u8> = None;
+        let mut halfmove_clock: Option<u32> = None;
+        let mut fullmove_number: Option<u32> = None;
 
         for (i, c) in fen.chars().enumerate() {
             if !c.is_ascii() {
@@ -78,7 +80,6 @@ impl Position {
                     }
                     2 => {
                         if c == '-' {
-                            stage += 1;
                             continue;
                         }
 
@@ -103,11 +104,23 @@ impl Position {
                         if c.is_alphabetic() && ep_file.is_none() {
                             ep_file = Some(c.to_ascii_lowercase() as u8 - b'a');
                         } else if let Some(ep_file) = ep_file {
+                            // the square a pawn skipped with a double push is on the 6th rank
+                            // when white is to move, and the 3rd when black is
+                            let rank = match pos.turn {
+                                Color::White => 6,
+                                Color::Black => 3,
+                            };
                             match c.to_digit(10) {
-                                Some(x) => {
+                                Some(x) if ep_file < 8 && x == rank => {
                                     pos.en_passant =
                                         Some((x as BoardIndex - 1) * 8 + ep_file as BoardIndex);
                                 }
+                                Some(x) => {
+                                    return Err(format!(
+                    "fen parsing (stage 3) en passant square '{}{}' must be on rank {}",
+                    (b'a' + ep_file) as char, x, rank
+                  ))
+                                }
                                 None => {
                                     return Err(format!(
                     "fen parsing (stage 3) encountered invalid symbol '{}' at index {}",
@@ -119,11 +132,68 @@ impl Position {
                             return Err("invalid en passant formatting".to_string());
                         }
                     }
-                    _ => break,
+                    4 | 5 => {
+                        let counter = if stage == 4 {
+                            &mut halfmove_clock
+                        } else {
+                            &mut fullmove_number
+                        };
+
+                        let digit = match c.to_digit(10) {
+                            Some(x) => x,
+                            None => {
+                                return Err(format!(
+                    "fen parsing (stage {}) encountered invalid symbol '{}' at index {}",
+                    stage, c, i
+                  ))
+                            }
+                        };
+
+                        *counter = Some(
+                            counter
+                                .unwrap_or(0)
+                                .checked_mul(10)
+                                .and_then(|x| x.checked_add(digit))
+                                .ok_or(format!(
+                                    "fen parsing (stage {}) move counter overflowed",
+                                    stage
+                                ))?,
+                        );
+                    }
+                    _ => {
+                        return Err(format!(
+                            "fen parsing encountered unexpected field '{}' at index {}",
+                            c, i
+                        ))
+                    }
                 }
             }
         }
 
+        // the move counters are optional, so four-field FENs fall back on the defaults
+        if let Some(halfmove_clock) = halfmove_clock {
+            pos.halfmove_clock = halfmove_clock;
+        }
+        if let Some(fullmove_number) = fullmove_number {
+            if fullmove_number == 0 {
+                return Err("fullmove number must start at 1".to_string());
+            }
+            pos.fullmove_number = fullmove_number;
+        }
+
+        // castling rights are dropped unless the king and rook are still on their home squares
+        let homes = pos.white.king & 1 << 4
+            | pos.white.rooks & (1 | 1 << 7)
+            | pos.black.king & 1 << 60
+            | pos.black.rooks & (1 << 56 | 1 << 63);
+        for index in [0, 4, 7, 56, 60, 63] {
+            if get_bit(homes, index) == 0 {
+                pos.castles &= !Castles::revoked_by(index);
+            }
+        }
+
+        pos.hash = pos.compute_hash();
+
         Ok(pos)
     }
 
@@ -212,6 +282,11 @@ impl Position {
             out.push('-');
         }
 
+        out.push_str(&format!(
+            " {} {}",
+            self.halfmove_clock, self.fullmove_number
+        ));
+
         out
     }
 }
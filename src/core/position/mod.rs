@@ -1,12 +1,17 @@
+use std::fmt;
+
 use crate::{
-    board::{clear_bit, get_bit, set_bit, Bits, Board, BoardIndex},
-    mov::Move,
+    board::{clear_bit, get_bit, set_bit, Bits, Board, BoardIndex, Castles},
+    mov::{gen::can_capture_en_passant, Move},
 };
 
+use self::zobrist::Zobrist;
+
 pub mod fen;
+pub mod zobrist;
 
 /// Black or white.
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Color {
     White = 1,
     Black = 0,
@@ -29,7 +34,7 @@ impl Color {
 }
 
 /// A piece on a chess board.
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Piece {
     Pawn,
     Knight,
@@ -42,7 +47,7 @@ pub enum Piece {
 pub type ColoredPiece = (Color, Piece);
 
 /// Piece positions for one color.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct PositionPieces {
     pub pawns: Bits,
     pub knights: Bits,
@@ -95,12 +100,12 @@ pub fn alg_to_index(s: &str) -> Result<BoardIndex, String> {
 
     let mut chars = s.chars();
 
-    let file = chars.next().unwrap() as u8 - b'a';
+    let file = (chars.next().unwrap() as u8).wrapping_sub(b'a');
     if file > 7 {
         return Err("invalid file char".to_string());
     }
 
-    let rank = chars.next().unwrap() as u8 - b'1';
+    let rank = (chars.next().unwrap() as u8).wrapping_sub(b'1');
     if rank > 7 {
         return Err("invalid rank char".to_string());
     }
@@ -124,31 +129,57 @@ pub fn index_to_alg(index: BoardIndex) -> String {
     out
 }
 
+/// The state lost by `Position::make_move`, used to restore the position in `unmake_move`.
+#[derive(Clone, Copy, Debug)]
+pub struct UndoInfo {
+    /// The captured piece, if there was one.
+    pub capture: Option<Piece>,
+    pub en_passant: Option<BoardIndex>,
+    pub castles: Bits,
+    pub halfmove_clock: u32,
+    pub fullmove_number: u32,
+    pub hash: u64,
+}
+
 /// An instance of a board position.
 /// Stores information like piece positions, available castles, and current turn count.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Position {
     pub white: PositionPieces,
     pub black: PositionPieces,
     pub en_passant: Option<BoardIndex>,
     pub castles: Bits,
     pub turn: Color,
+    /// The number of halfmoves since the last capture or pawn move.
+    pub halfmove_clock: u32,
+    /// The number of the current full move, starting at 1 and incremented after black moves.
+    pub fullmove_number: u32,
+    /// The Zobrist hash of the pieces, side to move, castles and, if it can be captured on, the
+    /// en passant square.
+    pub hash: u64,
 }
 
 impl Position {
     #[allow(dead_code)]
     pub const STANDARD_FEN: &'static str =
-        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 0";
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
 
     /// Returns this position, mirrored across the 4th and 5th rank. Clears the move cache.
     pub fn mirror(self: &Position) -> Position {
-        Position {
+        let mut pos = Position {
             white: self.black.mirror(),
             black: self.white.mirror(),
             en_passant: self.en_passant.map(Board::mirror_index),
             castles: Board::mirror_board(self.castles),
-            turn: self.turn.clone(),
-        }
+            turn: self.turn,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            hash: 0,
+        };
+        // the mirror keeps the side to move, so it can't be played on, and its en passant
+        // square is hashed as it stands rather than checked for a legal capture
+        pos.hash = pos.compute_hash_without_en_passant() ^ Zobrist::en_passant(pos.en_passant);
+        pos
     }
 
     pub fn get_all_pieces(&self) -> Bits {
@@ -157,7 +188,8 @@ impl Position {
 
     /// Sets the specified square to a `color` `piece`. Clears the move cache.
     fn set_square(&mut self, index: BoardIndex, color: Color, piece: Piece) {
-        self.clear_square(index);
+        self.pop_square(index);
+        self.hash ^= Zobrist::piece(color, piece, index);
         let pieces = match color {
             Color::Black => &mut self.black,
             Color::White => &mut self.white,
@@ -193,7 +225,7 @@ impl Position {
     }
 
     /// Returns the color and piece of a specified square or None if there is no piece there.
-    fn get_square(&self, index: BoardIndex) -> Option<ColoredPiece> {
+    pub fn get_square(&self, index: BoardIndex) -> Option<ColoredPiece> {
         // if there is no white piece here, this saves time
         if self.white.get_all_pieces() & (1_u64 << index) != 0 {
             if get_bit(self.white.pawns, index) != 0 {
@@ -247,6 +279,7 @@ impl Position {
         let piece = self.get_square(index)?;
 
         self.clear_square(index);
+        self.hash ^= Zobrist::piece(piece.0, piece.1, index);
         Some(piece)
     }
 
@@ -256,9 +289,37 @@ impl Position {
     /// Clears the move cache.
     pub fn apply_move(&self, mov: &Move) -> (Position, Option<Piece>) {
         let mut pos: Position = self.clone();
-        let capture: Option<Piece> = if mov.is_capture() {
+        let undo = pos.make_move(*mov);
+        (pos, undo.capture)
+    }
+
+    /// Applies the given move to the position in place.
+    /// Assumes the move is valid.
+    /// Returns the information needed to take the move back with `unmake_move`.
+    pub fn make_move(&mut self, mov: Move) -> UndoInfo {
+        let from = mov.get_from();
+        let to = mov.get_to();
+
+        let mut undo = UndoInfo {
+            capture: None,
+            en_passant: self.en_passant,
+            castles: self.castles,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            hash: self.hash,
+        };
+        self.hash ^= Zobrist::en_passant(self.en_passant_capture());
+
+        undo.capture = if mov.is_en_passant() {
+            // the captured pawn is behind the target square, not on it
+            Some(
+                self.pop_square(Position::en_passant_victim(self.turn, to))
+                    .expect("tried to capture an empty en passant square")
+                    .1,
+            )
+        } else if mov.is_capture() {
             Some(
-                pos.pop_square(mov.get_to())
+                self.pop_square(to)
                     .expect("tried to capture an empty square")
                     .1,
             )
@@ -266,43 +327,176 @@ impl Position {
             None
         };
 
-        match pos.pop_square(mov.get_from()) {
+        let moved = match self.pop_square(from) {
             Some((_, Piece::Pawn)) => {
                 let piece = match mov.get_promotion_piece() {
                     None => Piece::Pawn,
                     Some(p) => p,
                 };
-                pos.set_square(mov.get_to(), pos.turn.clone(), piece);
+                self.set_square(to, self.turn, piece);
+                Piece::Pawn
+            }
+            Some((_, p)) => {
+                self.set_square(to, self.turn, p);
+                p
             }
-            Some((_, p)) => pos.set_square(mov.get_to(), pos.turn.clone(), p),
             None => panic!(
                 "tried to apply an invalid move for {:?}: {} in position:\n{}",
-                pos.turn,
-                mov.to_string(),
-                self.to_string()
+                self.turn, mov, self
             ),
+        };
+
+        // the rook jumps over the king when castling
+        if mov.is_king_castle() {
+            self.pop_square(from + 3);
+            self.set_square(from + 1, self.turn, Piece::Rook);
+        } else if mov.is_queen_castle() {
+            self.pop_square(from - 4);
+            self.set_square(from - 1, self.turn, Piece::Rook);
+        }
+
+        // the en passant square is the one the pawn skipped over; it is hashed below, once the
+        // other side is to move and it is known whether it can capture there
+        self.en_passant = if mov.is_double_pawn_push() {
+            Some((from + to) / 2)
+        } else {
+            None
+        };
+
+        // moving a king or rook, or capturing a rook, loses castling rights
+        self.hash ^= Zobrist::castles(self.castles);
+        self.castles &= !(Castles::revoked_by(from) | Castles::revoked_by(to));
+        self.hash ^= Zobrist::castles(self.castles);
+
+        // pawn moves and captures reset the fifty-move clock
+        if undo.capture.is_some() || moved == Piece::Pawn {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+
+        if self.turn == Color::Black {
+            self.fullmove_number += 1;
         }
 
         // flip color
-        pos.turn.flip();
-        (pos, capture)
+        self.turn.flip();
+        self.hash ^= Zobrist::side();
+        self.hash ^= Zobrist::en_passant(self.en_passant_capture());
+        undo
+    }
+
+    /// Takes back a move that was applied with `make_move`, restoring the position exactly.
+    /// `mov` and `undo` must be the most recently made move and its undo record.
+    pub fn unmake_move(&mut self, mov: Move, undo: UndoInfo) {
+        let from = mov.get_from();
+        let to = mov.get_to();
+
+        self.turn.flip();
+
+        match self.pop_square(to) {
+            Some((_, p)) => {
+                let piece = if mov.is_promotion() { Piece::Pawn } else { p };
+                self.set_square(from, self.turn, piece);
+            }
+            None => panic!(
+                "tried to unmake an invalid move for {:?}: {} in position:\n{}",
+                self.turn, mov, self
+            ),
+        }
+
+        if mov.is_king_castle() {
+            self.pop_square(from + 1);
+            self.set_square(from + 3, self.turn, Piece::Rook);
+        } else if mov.is_queen_castle() {
+            self.pop_square(from - 1);
+            self.set_square(from - 4, self.turn, Piece::Rook);
+        }
+
+        if let Some(capture) = undo.capture {
+            let square = if mov.is_en_passant() {
+                Position::en_passant_victim(self.turn, to)
+            } else {
+                to
+            };
+            self.set_square(square, self.turn.opposite(), capture);
+        }
+
+        self.en_passant = undo.en_passant;
+        self.castles = undo.castles;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.fullmove_number = undo.fullmove_number;
+        self.hash = undo.hash;
+    }
+
+    /// Passes the turn without moving, as null-move pruning does. The side to move must not be
+    /// in check. Undo it with `unmake_null_move`.
+    pub fn make_null_move(&mut self) -> UndoInfo {
+        let undo = UndoInfo {
+            capture: None,
+            en_passant: self.en_passant,
+            castles: self.castles,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            hash: self.hash,
+        };
+
+        self.hash ^= Zobrist::en_passant(self.en_passant_capture());
+        self.en_passant = None;
+
+        self.halfmove_clock += 1;
+        if self.turn == Color::Black {
+            self.fullmove_number += 1;
+        }
+
+        self.turn.flip();
+        self.hash ^= Zobrist::side();
+        undo
+    }
+
+    /// Takes back a null move that was made with `make_null_move`.
+    pub fn unmake_null_move(&mut self, undo: UndoInfo) {
+        self.turn.flip();
+        self.en_passant = undo.en_passant;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.fullmove_number = undo.fullmove_number;
+        self.hash = undo.hash;
+    }
+
+    /// Returns the en passant square if the side to move can legally capture there. Otherwise
+    /// the square makes no difference to the game, so it is left out of the hash.
+    pub fn en_passant_capture(&self) -> Option<BoardIndex> {
+        self.en_passant.filter(|_| can_capture_en_passant(self))
+    }
+
+    /// Returns the square of the pawn captured when `turn` captures en passant onto `to`.
+    fn en_passant_victim(turn: Color, to: BoardIndex) -> BoardIndex {
+        match turn {
+            Color::White => to - 8,
+            Color::Black => to + 8,
+        }
     }
 }
 
 impl Default for Position {
     fn default() -> Self {
-        Position {
+        let mut pos = Position {
             white: PositionPieces::empty(),
             black: PositionPieces::empty(),
             en_passant: None,
             castles: 0,
             turn: Color::Black,
-        }
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            hash: 0,
+        };
+        pos.hash = pos.compute_hash();
+        pos
     }
 }
 
-impl ToString for Position {
-    fn to_string(&self) -> String {
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let mut out = String::new();
         let mut row = String::new();
 
@@ -335,6 +529,6 @@ impl ToString for Position {
             }
         }
 
-        out
+        write!(f, "{}", out)
     }
 }
@@ -0,0 +1,147 @@
+use crate::board::{get_bit, Bits, BoardIndex, Castles};
+
+use super::{Color, Piece, Position};
+
+/// Random keys for Zobrist hashing, generated at compile time from a fixed seed so that
+/// hashes are stable between runs.
+pub struct Zobrist {
+    /// Indexed by color, piece and square.
+    pieces: [[[u64; 64]; 6]; 2],
+    /// Toggled when black is to move.
+    black_to_move: u64,
+    /// One key per castling right: white king, white queen, black king, black queen.
+    castles: [u64; 4],
+    /// One key per en passant file.
+    en_passant: [u64; 8],
+}
+
+/// Advances the splitmix64 state and returns the next pseudo-random number.
+const fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+const fn generate_keys() -> Zobrist {
+    let mut state: u64 = 0x636865646461722e;
+    let mut keys = Zobrist {
+        pieces: [[[0; 64]; 6]; 2],
+        black_to_move: 0,
+        castles: [0; 4],
+        en_passant: [0; 8],
+    };
+
+    let mut color = 0;
+    while color < 2 {
+        let mut piece = 0;
+        while piece < 6 {
+            let mut square = 0;
+            while square < 64 {
+                keys.pieces[color][piece][square] = splitmix64(&mut state);
+                square += 1;
+            }
+            piece += 1;
+        }
+        color += 1;
+    }
+
+    keys.black_to_move = splitmix64(&mut state);
+
+    let mut i = 0;
+    while i < 4 {
+        keys.castles[i] = splitmix64(&mut state);
+        i += 1;
+    }
+
+    i = 0;
+    while i < 8 {
+        keys.en_passant[i] = splitmix64(&mut state);
+        i += 1;
+    }
+
+    keys
+}
+
+const KEYS: Zobrist = generate_keys();
+
+impl Zobrist {
+    /// Returns the key for a `color` `piece` on the specified square.
+    #[inline]
+    pub fn piece(color: Color, piece: Piece, index: BoardIndex) -> u64 {
+        KEYS.pieces[color as usize][piece as usize][index as usize]
+    }
+
+    /// Returns the key that is toggled whenever the side to move changes.
+    #[inline]
+    pub fn side() -> u64 {
+        KEYS.black_to_move
+    }
+
+    /// Returns the combined key for a set of castling rights.
+    pub fn castles(castles: Bits) -> u64 {
+        let mut key = 0;
+        for (i, right) in [
+            Castles::WHITE_KING,
+            Castles::WHITE_QUEEN,
+            Castles::BLACK_KING,
+            Castles::BLACK_QUEEN,
+        ]
+        .iter()
+        .enumerate()
+        {
+            if castles & right != 0 {
+                key ^= KEYS.castles[i];
+            }
+        }
+        key
+    }
+
+    /// Returns the key for an en passant square, or 0 if there is none.
+    #[inline]
+    pub fn en_passant(en_passant: Option<BoardIndex>) -> u64 {
+        match en_passant {
+            Some(index) => KEYS.en_passant[(index % 8) as usize],
+            None => 0,
+        }
+    }
+}
+
+impl Position {
+    /// Computes this position's Zobrist hash from scratch.
+    /// `make_move` keeps `hash` up to date incrementally, so this is only needed after
+    /// editing the bitboards directly or to verify the incremental hash.
+    pub fn compute_hash(&self) -> u64 {
+        self.compute_hash_without_en_passant() ^ Zobrist::en_passant(self.en_passant_capture())
+    }
+
+    /// Computes the hash of everything but the en passant square, which only counts if it can
+    /// be captured on.
+    pub fn compute_hash_without_en_passant(&self) -> u64 {
+        let mut hash = 0;
+
+        for (color, pieces) in [(Color::White, &self.white), (Color::Black, &self.black)] {
+            for (piece, board) in [
+                (Piece::Pawn, pieces.pawns),
+                (Piece::Knight, pieces.knights),
+                (Piece::Bishop, pieces.bishops),
+                (Piece::Rook, pieces.rooks),
+                (Piece::Queen, pieces.queens),
+                (Piece::King, pieces.king),
+            ] {
+                for index in 0..64 {
+                    if get_bit(board, index) != 0 {
+                        hash ^= Zobrist::piece(color, piece, index);
+                    }
+                }
+            }
+        }
+
+        if self.turn == Color::Black {
+            hash ^= Zobrist::side();
+        }
+
+        hash ^ Zobrist::castles(self.castles)
+    }
+}
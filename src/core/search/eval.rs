@@ -1,6 +1,147 @@
-use crate::position::{Color, Position};
+use std::ops::{AddAssign, Mul};
 
-use super::Score;
+use crate::{
+    board::Bits,
+    fill::{
+        king_fill, knight::knight_fill, north_pawn_attacks, slide::SlideFill, south_pawn_attacks,
+    },
+    mov::Move,
+    position::{Color, Piece, Position, PositionPieces},
+};
+
+use super::{
+    pst::{EG_TABLES, EG_VALUES, MAX_PHASE, MG_TABLES, MG_VALUES, PHASE_WEIGHTS},
+    Score,
+};
+
+/// A static evaluation, scoring the positions at the leaves of the search.
+///
+/// Any `FnMut(&Position) -> Score` is an evaluator, so plain functions such as
+/// `material_evaluator` can be searched with directly.
+pub trait Evaluator {
+    /// A short name, to tell evaluators apart.
+    fn name(&self) -> &str {
+        "custom"
+    }
+
+    /// Scores `pos` in centipawns for the side to move.
+    fn evaluate(&mut self, pos: &Position) -> Score;
+
+    /// Called with the root position before a search, so that incremental state can be
+    /// rebuilt.
+    fn reset(&mut self, _pos: &Position) {}
+
+    /// Called by the search just before `mov` is made on `pos`.
+    fn make_move(&mut self, _pos: &Position, _mov: Move) {}
+
+    /// Called by the search just after `mov` is unmade, with `pos` as it was before the move.
+    fn unmake_move(&mut self, _pos: &Position, _mov: Move) {}
+
+    /// Called by the search just before it passes the turn in `pos`.
+    fn make_null_move(&mut self, _pos: &Position) {}
+
+    /// Called by the search just after taking back a null move, with `pos` as it was before.
+    fn unmake_null_move(&mut self, _pos: &Position) {}
+
+    /// Returns the tunable parameters and their current values.
+    fn params(&self) -> Vec<EvalParam> {
+        Vec::new()
+    }
+
+    /// Sets the parameter called `name`.
+    fn set_param(&mut self, name: &str, _value: Score) -> Result<(), String> {
+        Err(format!("unknown parameter '{}'", name))
+    }
+
+    /// Breaks the evaluation of `pos` down into terms, which add up to it once tapered by
+    /// `game_phase`. By default the whole evaluation is a single term, the same in every phase.
+    fn explain(&mut self, pos: &Position) -> Vec<EvalTerm> {
+        let score = self.evaluate(pos);
+        let white = match pos.turn {
+            Color::White => score,
+            Color::Black => -score,
+        };
+        vec![EvalTerm::new(
+            self.name(),
+            Phased::flat(white),
+            Phased::default(),
+        )]
+    }
+}
+
+impl<F: FnMut(&Position) -> Score> Evaluator for F {
+    fn evaluate(&mut self, pos: &Position) -> Score {
+        self(pos)
+    }
+}
+
+/// A tunable parameter of an evaluator.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EvalParam {
+    pub name: String,
+    pub value: Score,
+    pub min: Score,
+    pub max: Score,
+}
+
+/// A score split into its middlegame and endgame parts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Phased {
+    pub mg: Score,
+    pub eg: Score,
+}
+
+impl Phased {
+    /// Returns a score that doesn't depend on the phase.
+    pub fn flat(score: Score) -> Phased {
+        Phased {
+            mg: score,
+            eg: score,
+        }
+    }
+
+    /// Blends the two parts by `phase`, as returned by `game_phase`.
+    pub fn taper(self, phase: i32) -> Score {
+        (self.mg * phase + self.eg * (MAX_PHASE - phase)) / MAX_PHASE
+    }
+}
+
+impl AddAssign for Phased {
+    fn add_assign(&mut self, other: Phased) {
+        self.mg += other.mg;
+        self.eg += other.eg;
+    }
+}
+
+impl Mul<u32> for Phased {
+    type Output = Phased;
+
+    fn mul(self, count: u32) -> Phased {
+        Phased {
+            mg: self.mg * count as Score,
+            eg: self.eg * count as Score,
+        }
+    }
+}
+
+/// One term of an evaluation, scored separately for each side. The evaluation from white's
+/// point of view is the sum of `white - black` over all terms, each side tapered separately.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EvalTerm {
+    pub name: String,
+    pub white: Phased,
+    pub black: Phased,
+}
+
+impl EvalTerm {
+    pub fn new(name: &str, white: Phased, black: Phased) -> EvalTerm {
+        EvalTerm {
+            name: name.to_string(),
+            white,
+            black,
+        }
+    }
+}
 
 pub fn piece_evaluator(pos: &Position) -> Score {
     let abs_score: Score =
@@ -10,3 +151,288 @@ pub fn piece_evaluator(pos: &Position) -> Score {
         Color::Black => -abs_score,
     }
 }
+
+/// The value of each piece in centipawns, indexed by `Piece`. The king is never traded, so it
+/// has no material value.
+pub const PIECE_VALUES: [Score; 6] = [100, 300, 300, 500, 900, 0];
+
+/// Scores the material balance in centipawns.
+pub fn material_evaluator(pos: &Position) -> Score {
+    MaterialEvaluator::default().evaluate(pos)
+}
+
+/// Counts material, with tunable piece values.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MaterialEvaluator {
+    /// Indexed by `Piece`.
+    pub values: [Score; 6],
+}
+
+impl Default for MaterialEvaluator {
+    fn default() -> MaterialEvaluator {
+        MaterialEvaluator {
+            values: PIECE_VALUES,
+        }
+    }
+}
+
+impl MaterialEvaluator {
+    const PARAMS: [&'static str; 5] = ["Pawn", "Knight", "Bishop", "Rook", "Queen"];
+
+    fn material(&self, pieces: &PositionPieces) -> Score {
+        piece_boards(pieces)
+            .iter()
+            .zip(self.values)
+            .map(|(board, value)| board.count_ones() as Score * value)
+            .sum()
+    }
+}
+
+impl Evaluator for MaterialEvaluator {
+    fn name(&self) -> &str {
+        "material"
+    }
+
+    fn evaluate(&mut self, pos: &Position) -> Score {
+        let abs_score = self.material(&pos.white) - self.material(&pos.black);
+        match pos.turn {
+            Color::White => abs_score,
+            Color::Black => -abs_score,
+        }
+    }
+
+    fn params(&self) -> Vec<EvalParam> {
+        MaterialEvaluator::PARAMS
+            .iter()
+            .zip(self.values)
+            .map(|(name, value)| EvalParam {
+                name: name.to_string(),
+                value,
+                min: 0,
+                max: 2000,
+            })
+            .collect()
+    }
+
+    fn set_param(&mut self, name: &str, value: Score) -> Result<(), String> {
+        let piece = MaterialEvaluator::PARAMS
+            .iter()
+            .position(|param| param.eq_ignore_ascii_case(name))
+            .ok_or(format!("unknown parameter '{}'", name))?;
+        if !(0..=2000).contains(&value) {
+            return Err(format!("{} must be between 0 and 2000", name));
+        }
+        self.values[piece] = value;
+        Ok(())
+    }
+
+    fn explain(&mut self, pos: &Position) -> Vec<EvalTerm> {
+        vec![EvalTerm::new(
+            "Material",
+            Phased::flat(self.material(&pos.white)),
+            Phased::flat(self.material(&pos.black)),
+        )]
+    }
+}
+
+/// Returns the game phase, from `MAX_PHASE` with every piece on the board down to 0 with only
+/// kings and pawns left.
+pub fn game_phase(pos: &Position) -> i32 {
+    let phase = |pieces: &PositionPieces| {
+        piece_boards(pieces)
+            .iter()
+            .zip(PHASE_WEIGHTS)
+            .map(|(board, weight)| board.count_ones() as i32 * weight)
+            .sum::<i32>()
+    };
+    (phase(&pos.white) + phase(&pos.black)).min(MAX_PHASE)
+}
+
+/// Scores material, piece placement, pawn structure, mobility and king safety in centipawns,
+/// blending the middlegame and endgame scores by the game phase.
+pub fn pst_evaluator(pos: &Position) -> Score {
+    let phase = game_phase(pos);
+    let abs_score = pst_terms(pos)
+        .iter()
+        .map(|(_, white, black)| white.taper(phase) - black.taper(phase))
+        .sum::<Score>();
+    match pos.turn {
+        Color::White => abs_score,
+        Color::Black => -abs_score,
+    }
+}
+
+/// `pst_evaluator` as an `Evaluator`, which can explain itself.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PstEvaluator;
+
+impl Evaluator for PstEvaluator {
+    fn name(&self) -> &str {
+        "pst"
+    }
+
+    fn evaluate(&mut self, pos: &Position) -> Score {
+        pst_evaluator(pos)
+    }
+
+    fn explain(&mut self, pos: &Position) -> Vec<EvalTerm> {
+        pst_terms(pos)
+            .iter()
+            .map(|&(name, white, black)| EvalTerm::new(name, white, black))
+            .collect()
+    }
+}
+
+/// Returns the terms of `pos` scored by `pst_evaluator`.
+fn pst_terms(pos: &Position) -> [(&'static str, Phased, Phased); 5] {
+    // (material, placement) for one side; the tables are drawn from white's side
+    let score = |pieces: &PositionPieces, flip: usize| {
+        let (mut material, mut placement) = (Phased::default(), Phased::default());
+        for (piece, mut board) in piece_boards(pieces).into_iter().enumerate() {
+            while board != 0 {
+                let index = board.trailing_zeros() as usize ^ flip;
+                material.mg += MG_VALUES[piece];
+                material.eg += EG_VALUES[piece];
+                placement.mg += MG_TABLES[piece][index];
+                placement.eg += EG_TABLES[piece][index];
+                board &= board - 1;
+            }
+        }
+        (material, placement)
+    };
+
+    let (white_material, white_placement) = score(&pos.white, 56);
+    let (black_material, black_placement) = score(&pos.black, 0);
+    let [white_pawns, white_mobility, white_king] = positional_terms(&pos.white, &pos.black);
+    let [black_pawns, black_mobility, black_king] =
+        positional_terms(&pos.black.mirror(), &pos.white.mirror());
+    [
+        ("Material", white_material, black_material),
+        ("Placement", white_placement, black_placement),
+        ("Pawns", white_pawns, black_pawns),
+        ("Mobility", white_mobility, black_mobility),
+        ("King safety", white_king, black_king),
+    ]
+}
+
+const DOUBLED_PAWN: Phased = Phased { mg: -10, eg: -25 };
+const ISOLATED_PAWN: Phased = Phased { mg: -10, eg: -15 };
+
+/// The bonus for a passed pawn, indexed by the rank it has reached, counted from its own side.
+const PASSED_PAWN: [Phased; 8] = [
+    Phased { mg: 0, eg: 0 },
+    Phased { mg: 0, eg: 10 },
+    Phased { mg: 5, eg: 15 },
+    Phased { mg: 10, eg: 25 },
+    Phased { mg: 20, eg: 45 },
+    Phased { mg: 35, eg: 75 },
+    Phased { mg: 60, eg: 120 },
+    Phased { mg: 0, eg: 0 },
+];
+
+/// The bonus for each square a piece can move to that no enemy pawn attacks, indexed by `Piece`.
+const MOBILITY: [Phased; 6] = [
+    Phased { mg: 0, eg: 0 },
+    Phased { mg: 4, eg: 4 },
+    Phased { mg: 5, eg: 5 },
+    Phased { mg: 2, eg: 4 },
+    Phased { mg: 1, eg: 2 },
+    Phased { mg: 0, eg: 0 },
+];
+
+/// The bonus for each pawn in front of the king, one and two ranks ahead of it. Only counts in
+/// the middlegame.
+const PAWN_SHIELD: [Score; 2] = [10, 5];
+
+/// The penalty for each square around the king that an enemy piece attacks, indexed by
+/// `Piece`. Only counts in the middlegame.
+const KING_ATTACK: [Score; 6] = [0, 6, 6, 8, 12, 0];
+
+/// Returns the pawn structure, mobility and king safety of `us` against `them`, with `us` moving
+/// up the board as white does.
+fn positional_terms(us: &PositionPieces, them: &PositionPieces) -> [Phased; 3] {
+    let north_fill = |board| SlideFill::north_occl(board, !0);
+    let south_fill = |board| SlideFill::south_occl(board, !0);
+
+    // doubled pawns have another pawn behind them, and isolated ones no pawns on either side
+    let mut pawns = Phased::default();
+    let files = north_fill(south_fill(us.pawns));
+    let doubled = us.pawns & north_fill(SlideFill::north_one(us.pawns));
+    let isolated = us.pawns & !(SlideFill::east_one(files) | SlideFill::west_one(files));
+    pawns += DOUBLED_PAWN * doubled.count_ones();
+    pawns += ISOLATED_PAWN * isolated.count_ones();
+
+    // a passed pawn has no pawn ahead of it, and no enemy pawn that can take it on the way
+    let stoppers =
+        us.pawns | them.pawns | SlideFill::east_one(them.pawns) | SlideFill::west_one(them.pawns);
+    let mut passed = us.pawns & !south_fill(SlideFill::south_one(stoppers));
+    while passed != 0 {
+        pawns += PASSED_PAWN[passed.trailing_zeros() as usize / 8];
+        passed &= passed - 1;
+    }
+
+    let open = !(us.get_all_pieces() | them.get_all_pieces());
+    let mut mobility = Phased::default();
+    let safe = !us.get_all_pieces() & !south_pawn_attacks(them.pawns);
+    for_each_attack(us, open, |piece, attacks| {
+        mobility += MOBILITY[piece as usize] * (attacks & safe).count_ones();
+    });
+
+    let mut king = Phased::default();
+    let shield = north_pawn_attacks(us.king) | SlideFill::north_one(us.king);
+    king.mg += PAWN_SHIELD[0] * (us.pawns & shield).count_ones() as Score
+        + PAWN_SHIELD[1] * (us.pawns & SlideFill::north_one(shield)).count_ones() as Score;
+    let zone = king_fill(us.king);
+    for_each_attack(them, open, |piece, attacks| {
+        king.mg -= KING_ATTACK[piece as usize] * (attacks & zone).count_ones() as Score;
+    });
+
+    [pawns, mobility, king]
+}
+
+/// Calls `f` with each knight, bishop, rook and queen in `pieces` and the squares it attacks.
+fn for_each_attack(pieces: &PositionPieces, open: Bits, mut f: impl FnMut(Piece, Bits)) {
+    let diagonal = |square| {
+        SlideFill::ne_attacks(square, open)
+            | SlideFill::nw_attacks(square, open)
+            | SlideFill::se_attacks(square, open)
+            | SlideFill::sw_attacks(square, open)
+    };
+    let orthogonal = |square| {
+        SlideFill::north_attacks(square, open)
+            | SlideFill::south_attacks(square, open)
+            | SlideFill::east_attacks(square, open)
+            | SlideFill::west_attacks(square, open)
+    };
+
+    for (piece, mut board) in [
+        (Piece::Knight, pieces.knights),
+        (Piece::Bishop, pieces.bishops),
+        (Piece::Rook, pieces.rooks),
+        (Piece::Queen, pieces.queens),
+    ] {
+        while board != 0 {
+            let square = board & board.wrapping_neg();
+            let attacks = match piece {
+                Piece::Knight => knight_fill(square),
+                Piece::Bishop => diagonal(square),
+                Piece::Rook => orthogonal(square),
+                _ => diagonal(square) | orthogonal(square),
+            };
+            f(piece, attacks);
+            board &= board - 1;
+        }
+    }
+}
+
+/// Returns the bitboards of `pieces`, indexed by `Piece`.
+pub(crate) fn piece_boards(pieces: &PositionPieces) -> [Bits; 6] {
+    [
+        pieces.pawns,
+        pieces.knights,
+        pieces.bishops,
+        pieces.rooks,
+        pieces.queens,
+        pieces.king,
+    ]
+}
@@ -0,0 +1,76 @@
+use std::time::Duration;
+
+use crate::position::Color;
+
+use super::MAX_DEPTH;
+
+/// How many moves are assumed to be left when the time control doesn't say.
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+
+/// Time kept in reserve so that the engine never loses on time while replying.
+const MOVE_OVERHEAD: Duration = Duration::from_millis(50);
+
+/// When a search should stop. All limits are optional; with none the search runs until it
+/// reaches `MAX_DEPTH` or is stopped.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SearchLimits {
+    /// The deepest iteration to search, in plies.
+    pub depth: Option<u8>,
+    /// The number of nodes after which to stop.
+    pub nodes: Option<u64>,
+    /// The exact time to spend on this move.
+    pub movetime: Option<Duration>,
+    pub wtime: Option<Duration>,
+    pub btime: Option<Duration>,
+    pub winc: Option<Duration>,
+    pub binc: Option<Duration>,
+    /// The number of moves until the next time control.
+    pub movestogo: Option<u32>,
+    /// Search for a mate in this many moves.
+    pub mate: Option<u32>,
+    /// Search until stopped, ignoring the clock.
+    pub infinite: bool,
+}
+
+impl SearchLimits {
+    /// Returns limits for a search to a fixed depth.
+    pub fn depth(depth: u8) -> SearchLimits {
+        SearchLimits {
+            depth: Some(depth),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the deepest iteration to search.
+    pub fn max_depth(&self) -> u8 {
+        // a mate in n moves is found within 2n - 1 plies
+        let mate_depth = self.mate.map_or(MAX_DEPTH, |mate| {
+            mate.saturating_mul(2)
+                .saturating_sub(1)
+                .min(MAX_DEPTH as u32) as u8
+        });
+        self.depth
+            .unwrap_or(MAX_DEPTH)
+            .min(mate_depth)
+            .min(MAX_DEPTH)
+    }
+
+    /// Returns how long `turn` may think about this move, or `None` if there is no time limit.
+    pub fn time_budget(&self, turn: Color) -> Option<Duration> {
+        if self.infinite {
+            return None;
+        }
+        if self.movetime.is_some() {
+            return self.movetime;
+        }
+
+        let (time, inc) = match turn {
+            Color::White => (self.wtime?, self.winc.unwrap_or_default()),
+            Color::Black => (self.btime?, self.binc.unwrap_or_default()),
+        };
+        let moves_to_go = self.movestogo.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+
+        let budget = time / moves_to_go + inc * 3 / 4;
+        Some(budget.min(time.saturating_sub(MOVE_OVERHEAD)))
+    }
+}
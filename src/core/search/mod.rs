@@ -1,4 +1,54 @@
 pub mod eval;
+pub mod limits;
+pub mod options;
+pub mod order;
+pub mod pst;
+pub mod see;
+pub mod trace;
 pub mod tree;
+pub mod tt;
 
 pub type Score = i32;
+
+/// The deepest the search will ever go, in plies.
+pub const MAX_DEPTH: u8 = 64;
+
+/// Bounds every score, so that it can be negated safely.
+pub const INFINITY: Score = 32_000;
+
+/// The score for delivering mate right now. Mate in n plies scores `MATE - n`, and being
+/// mated in n plies scores `-MATE + n`, so that shorter mates are preferred.
+pub const MATE: Score = 30_000;
+
+/// Scores beyond this are mate scores, found at most `MAX_DEPTH` plies from the root. Neither
+/// the search nor quiescence goes deeper, so no mate score crosses it.
+pub const MATE_THRESHOLD: Score = MATE - MAX_DEPTH as Score - 1;
+
+/// The score of a drawn position, such as stalemate.
+pub const DRAW: Score = 0;
+
+/// Returns the score for delivering mate `ply` plies from the root.
+pub fn mate_in(ply: u8) -> Score {
+    MATE - ply as Score
+}
+
+/// Returns the score for being mated `ply` plies from the root.
+pub fn mated_in(ply: u8) -> Score {
+    -MATE + ply as Score
+}
+
+pub fn is_mate_score(score: Score) -> bool {
+    score.abs() > MATE_THRESHOLD
+}
+
+/// Returns the number of moves until mate if `score` is a mate score, as UCI reports it:
+/// positive if the side to move mates, negative if it is mated.
+pub fn mate_distance(score: Score) -> Option<i32> {
+    if !is_mate_score(score) {
+        None
+    } else if score > 0 {
+        Some((MATE - score + 1) / 2)
+    } else {
+        Some(-(MATE + score) / 2)
+    }
+}
@@ -0,0 +1,140 @@
+use super::Score;
+
+/// Reaches one of the fields of `SearchOptions`.
+type Field = fn(&mut SearchOptions) -> &mut Score;
+
+/// Switches for the selective parts of the search, so that each can be turned off to measure
+/// what it is worth.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchOptions {
+    /// Principal variation search: every move after the first is searched with a zero window
+    /// around alpha, and only searched again with the full window if it turns out better.
+    pub pvs: bool,
+    /// Null-move pruning: if passing the turn still fails high in a shallower search, the node
+    /// is cut off without searching its moves.
+    pub null_move: bool,
+    /// Late move reductions: quiet moves ordered late are searched less deeply, and only
+    /// searched again at full depth if they beat alpha.
+    pub lmr: bool,
+    /// Aspiration windows: each iteration is searched with a window around the score of the
+    /// previous one, which is widened whenever the score falls outside it.
+    pub aspiration: bool,
+    /// Half the width of the first aspiration window, in centipawns.
+    pub aspiration_window: Score,
+    /// Check extensions: moves that give check are searched a ply deeper.
+    pub check_extensions: bool,
+    /// Singular extensions: the TT move is searched a ply deeper if every other move fails
+    /// well below its score in a shallower search.
+    pub singular_extensions: bool,
+    /// Futility pruning: near the leaves, quiet moves are skipped if the static evaluation is so
+    /// far below alpha that they can't plausibly raise it.
+    pub futility: bool,
+    /// How far below alpha the static evaluation has to be per ply of depth, in centipawns.
+    pub futility_margin: Score,
+    /// Reverse futility pruning: near the leaves, a node is cut off if the static evaluation is
+    /// so far above beta that no reply can plausibly bring it back.
+    pub reverse_futility: bool,
+    /// How far above beta the static evaluation has to be per ply of depth, in centipawns.
+    pub reverse_futility_margin: Score,
+    /// Razoring: near the leaves, a node whose static evaluation is far below alpha is only
+    /// searched for captures, and cut off if they don't help either.
+    pub razoring: bool,
+    /// How far below alpha the static evaluation has to be per ply of depth, in centipawns.
+    pub razoring_margin: Score,
+    /// SEE pruning: quiescence skips captures that lose material by static exchange
+    /// evaluation.
+    pub see_pruning: bool,
+}
+
+impl Default for SearchOptions {
+    fn default() -> SearchOptions {
+        SearchOptions {
+            pvs: true,
+            null_move: true,
+            lmr: true,
+            aspiration: true,
+            aspiration_window: 25,
+            check_extensions: true,
+            singular_extensions: true,
+            futility: true,
+            futility_margin: 100,
+            reverse_futility: true,
+            reverse_futility_margin: 80,
+            razoring: true,
+            razoring_margin: 200,
+            see_pruning: true,
+        }
+    }
+}
+
+impl SearchOptions {
+    /// Returns options for a plain alpha-beta search, with everything turned off.
+    pub fn none() -> SearchOptions {
+        SearchOptions {
+            pvs: false,
+            null_move: false,
+            lmr: false,
+            aspiration: false,
+            check_extensions: false,
+            singular_extensions: false,
+            futility: false,
+            reverse_futility: false,
+            razoring: false,
+            see_pruning: false,
+            ..Default::default()
+        }
+    }
+
+    /// The tunable parameters: their names, their ranges, and the fields that hold them.
+    const PARAMS: &'static [(&'static str, Score, Score, Field)] = &[
+        ("AspirationWindow", 1, 1000, |options| {
+            &mut options.aspiration_window
+        }),
+        ("FutilityMargin", 0, 1000, |options| {
+            &mut options.futility_margin
+        }),
+        ("ReverseFutilityMargin", 0, 1000, |options| {
+            &mut options.reverse_futility_margin
+        }),
+        ("RazoringMargin", 0, 1000, |options| {
+            &mut options.razoring_margin
+        }),
+    ];
+
+    /// Returns the tunable parameters and their current values.
+    pub fn params(&self) -> Vec<SearchParam> {
+        // the fields are reached mutably, so read them from a copy
+        let mut options = self.clone();
+        SearchOptions::PARAMS
+            .iter()
+            .map(|&(name, min, max, field)| SearchParam {
+                name: name.to_string(),
+                value: *field(&mut options),
+                min,
+                max,
+            })
+            .collect()
+    }
+
+    /// Sets the parameter called `name`, ignoring case.
+    pub fn set_param(&mut self, name: &str, value: Score) -> Result<(), String> {
+        let &(name, min, max, field) = SearchOptions::PARAMS
+            .iter()
+            .find(|(param, _, _, _)| param.eq_ignore_ascii_case(name))
+            .ok_or(format!("unknown parameter '{}'", name))?;
+        if !(min..=max).contains(&value) {
+            return Err(format!("{} must be between {} and {}", name, min, max));
+        }
+        *field(self) = value;
+        Ok(())
+    }
+}
+
+/// A tunable parameter of the search.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchParam {
+    pub name: String,
+    pub value: Score,
+    pub min: Score,
+    pub max: Score,
+}
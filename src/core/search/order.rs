@@ -0,0 +1,169 @@
+use crate::{
+    mov::{list::MoveList, Move},
+    position::{Color, Piece, Position},
+};
+
+use super::{see::see_ge, Score, MAX_DEPTH};
+
+/// Ordering scores for each kind of move. Captures and promotions add their MVV-LVA score, and
+/// quiet moves score their history, which stays below the killers. Captures that lose material
+/// come after every quiet move.
+const TT_MOVE_SCORE: Score = 1_000_000;
+const CAPTURE_SCORE: Score = 100_000;
+const LOSING_CAPTURE_SCORE: Score = -100_000;
+const KILLER_SCORES: [Score; 2] = [90_000, 80_000];
+const MAX_HISTORY: Score = 50_000;
+
+/// Scores a capture or promotion for ordering: Most Valuable Victim, then Least Valuable
+/// Attacker. Promotions score as if they captured the piece they promote to.
+pub fn mvv_lva(pos: &Position, mov: &Move) -> Score {
+    let victim = if mov.is_en_passant() {
+        Some(Piece::Pawn)
+    } else {
+        pos.get_square(mov.get_to()).map(|(_, piece)| piece)
+    };
+    let attacker = pos
+        .get_square(mov.get_from())
+        .map_or(Piece::Pawn, |(_, piece)| piece);
+
+    let mut score = victim.map_or(0, |victim| (victim as Score + 1) * 8 - attacker as Score);
+    if let Some(promotion) = mov.get_promotion_piece() {
+        score += promotion as Score * 8;
+    }
+    score
+}
+
+/// What the search has learnt about which quiet moves are good, used to order moves.
+pub struct MoveOrderer {
+    /// Two quiet moves per ply that recently caused a beta cutoff there.
+    killers: [[Option<Move>; 2]; MAX_DEPTH as usize],
+    /// How often each quiet move caused a cutoff, by color and butterfly index.
+    history: Box<[[Score; 4096]; 2]>,
+}
+
+impl MoveOrderer {
+    pub fn new() -> MoveOrderer {
+        MoveOrderer {
+            killers: [[None; 2]; MAX_DEPTH as usize],
+            history: Box::new([[0; 4096]; 2]),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = MoveOrderer::new();
+    }
+
+    /// Scores a move for ordering: the transposition table move first, then captures and
+    /// promotions by MVV-LVA, then killers, then quiet moves by history, then captures and
+    /// promotions that lose material by static exchange evaluation.
+    pub fn score(&self, pos: &Position, mov: &Move, tt_move: Option<Move>, ply: u8) -> Score {
+        if tt_move == Some(*mov) {
+            return TT_MOVE_SCORE;
+        }
+        if mov.is_capture() || mov.is_promotion() {
+            let score = if see_ge(pos, *mov, 0) {
+                CAPTURE_SCORE
+            } else {
+                LOSING_CAPTURE_SCORE
+            };
+            return score + mvv_lva(pos, mov);
+        }
+
+        let killers = self.killers.get(ply as usize).copied().unwrap_or_default();
+        match killers.iter().position(|killer| *killer == Some(*mov)) {
+            Some(slot) => KILLER_SCORES[slot],
+            None => self.history[pos.turn as usize][mov.get_butterfly_index() as usize],
+        }
+    }
+
+    /// Records a quiet move that caused a beta cutoff at `depth`, `ply` plies from the root.
+    pub fn record_cutoff(&mut self, color: Color, mov: Move, ply: u8, depth: u8) {
+        if let Some(killers) = self.killers.get_mut(ply as usize) {
+            if killers[0] != Some(mov) {
+                killers[1] = killers[0];
+                killers[0] = Some(mov);
+            }
+        }
+
+        let history = &mut self.history[color as usize];
+        let entry = &mut history[mov.get_butterfly_index() as usize];
+        *entry += depth as Score * depth as Score;
+
+        // keep history below the killers, favouring recent cutoffs
+        if *entry > MAX_HISTORY {
+            history.iter_mut().for_each(|score| *score /= 2);
+        }
+    }
+
+    /// Returns a picker that yields `moves` best first.
+    pub fn picker(
+        &self,
+        pos: &Position,
+        moves: MoveList,
+        tt_move: Option<Move>,
+        ply: u8,
+    ) -> MovePicker {
+        let mut scores = [0; 256];
+        for (score, mov) in scores.iter_mut().zip(&moves.get_moves()[..moves.count()]) {
+            *score = self.score(pos, mov, tt_move, ply);
+        }
+
+        MovePicker {
+            moves,
+            scores,
+            index: 0,
+        }
+    }
+}
+
+impl Default for MoveOrderer {
+    fn default() -> Self {
+        MoveOrderer::new()
+    }
+}
+
+/// Yields the moves of a `MoveList` highest score first. Each move is found by a selection
+/// sort step as it is asked for, since a cutoff often means most of them are never needed.
+pub struct MovePicker {
+    moves: MoveList,
+    scores: [Score; 256],
+    index: usize,
+}
+
+impl MovePicker {
+    /// Returns a picker over captures and promotions ordered by MVV-LVA alone.
+    pub fn captures(pos: &Position, moves: MoveList) -> MovePicker {
+        let mut scores = [0; 256];
+        for (score, mov) in scores.iter_mut().zip(&moves.get_moves()[..moves.count()]) {
+            *score = mvv_lva(pos, mov);
+        }
+
+        MovePicker {
+            moves,
+            scores,
+            index: 0,
+        }
+    }
+}
+
+impl Iterator for MovePicker {
+    type Item = Move;
+
+    fn next(&mut self) -> Option<Move> {
+        let count = self.moves.count();
+        if self.index >= count {
+            return None;
+        }
+
+        // on ties, keep the generator's order
+        let best = (self.index..count)
+            .rev()
+            .max_by_key(|&i| self.scores[i])
+            .expect("there is a move left");
+        self.scores.swap(self.index, best);
+        self.moves.get_moves_mut().swap(self.index, best);
+
+        self.index += 1;
+        Some(self.moves.get_moves()[self.index - 1])
+    }
+}
@@ -0,0 +1,199 @@
+use crate::{
+    board::Bits,
+    fill::{
+        king_fill, knight::knight_fill, north_pawn_attacks, slide::SlideFill, south_pawn_attacks,
+    },
+    mov::Move,
+    position::{Color, Piece, Position},
+};
+
+use super::{
+    eval::{piece_boards, PIECE_VALUES},
+    Score,
+};
+
+/// The value of each piece in an exchange, indexed by `Piece`. The king can only take last,
+/// so its value only has to outweigh everything else.
+const SEE_VALUES: [Score; 6] = [
+    PIECE_VALUES[0],
+    PIECE_VALUES[1],
+    PIECE_VALUES[2],
+    PIECE_VALUES[3],
+    PIECE_VALUES[4],
+    10_000,
+];
+
+/// Static exchange evaluation: returns the material the side to move gains by playing `mov`,
+/// if both sides then keep recapturing on its destination with their least valuable piece for
+/// as long as it pays.
+pub fn see(pos: &Position, mov: Move) -> Score {
+    if mov.is_castle() {
+        return 0;
+    }
+
+    let mut exchange = Exchange::new(pos, mov);
+    // gains[n] is what the side making the nth capture has won if it isn't recaptured
+    let mut gains = [0; 32];
+    gains[0] = captured_value(pos, mov);
+    let mut on_square = moved_value(pos, mov);
+    let mut color = pos.turn.opposite();
+    let mut depth = 0;
+
+    while let Some(piece) = exchange.capture(color) {
+        depth += 1;
+        gains[depth] = on_square - gains[depth - 1];
+        on_square = SEE_VALUES[piece as usize];
+        color = color.opposite();
+    }
+
+    // either side may stop recapturing once it would lose by going on
+    while depth > 0 {
+        gains[depth - 1] = -(-gains[depth - 1]).max(gains[depth]);
+        depth -= 1;
+    }
+    gains[0]
+}
+
+/// Returns whether `see(pos, mov) >= threshold`, stopping as soon as the answer is known.
+pub fn see_ge(pos: &Position, mov: Move, threshold: Score) -> bool {
+    if mov.is_castle() {
+        return threshold <= 0;
+    }
+
+    // what the side that captured last is ahead of the threshold by, if it isn't recaptured
+    let mut swap = captured_value(pos, mov) - threshold;
+    if swap < 0 {
+        return false;
+    }
+    // and what it is ahead by if it is, with the turn passing to the other side
+    swap = moved_value(pos, mov) - swap;
+    if swap <= 0 {
+        return true;
+    }
+
+    let mut exchange = Exchange::new(pos, mov);
+    let mut color = pos.turn.opposite();
+    let mut wins = true;
+    while let Some(piece) = exchange.capture(color) {
+        wins = !wins;
+        swap = SEE_VALUES[piece as usize] - swap;
+        if swap < wins as Score {
+            break;
+        }
+        color = color.opposite();
+    }
+    wins
+}
+
+/// Returns the value of the piece `mov` captures, plus what it gains by promoting.
+fn captured_value(pos: &Position, mov: Move) -> Score {
+    let captured = if mov.is_en_passant() {
+        Some(Piece::Pawn)
+    } else {
+        pos.get_square(mov.get_to()).map(|(_, piece)| piece)
+    };
+    let mut value = captured.map_or(0, |piece| SEE_VALUES[piece as usize]);
+    if let Some(promotion) = mov.get_promotion_piece() {
+        value += SEE_VALUES[promotion as usize] - SEE_VALUES[Piece::Pawn as usize];
+    }
+    value
+}
+
+/// Returns the value of the piece that stands on the destination of `mov` once it is made.
+fn moved_value(pos: &Position, mov: Move) -> Score {
+    let piece = mov
+        .get_promotion_piece()
+        .or_else(|| pos.get_square(mov.get_from()).map(|(_, piece)| piece));
+    piece.map_or(0, |piece| SEE_VALUES[piece as usize])
+}
+
+/// The pieces of both colors that can still take part in an exchange on one square.
+struct Exchange<'a> {
+    pos: &'a Position,
+    square: Bits,
+    occupied: Bits,
+    attackers: Bits,
+}
+
+impl<'a> Exchange<'a> {
+    /// Starts the exchange that `mov` begins, with the moving piece and anything it captures
+    /// already off the board.
+    fn new(pos: &'a Position, mov: Move) -> Exchange<'a> {
+        let square = 1 << mov.get_to();
+        let mut occupied = pos.get_all_pieces() & !(1 << mov.get_from()) & !square;
+        if mov.is_en_passant() {
+            // the captured pawn is beside the destination, on the mover's side of it
+            occupied &= match pos.turn {
+                Color::White => !SlideFill::south_one(square),
+                Color::Black => !SlideFill::north_one(square),
+            };
+        }
+
+        let mut exchange = Exchange {
+            pos,
+            square,
+            occupied,
+            attackers: 0,
+        };
+        exchange.attackers = exchange.attackers_to();
+        exchange
+    }
+
+    /// Returns every piece on the occupied squares that attacks the square. Sliders are found
+    /// through the occupancy as it stands, so that a piece lined up behind one that has
+    /// captured joins the exchange.
+    fn attackers_to(&self) -> Bits {
+        let (white, black) = (&self.pos.white, &self.pos.black);
+        let open = !self.occupied;
+        let square = self.square;
+
+        let orthogonal = SlideFill::north_attacks(square, open)
+            | SlideFill::south_attacks(square, open)
+            | SlideFill::east_attacks(square, open)
+            | SlideFill::west_attacks(square, open);
+        let diagonal = SlideFill::ne_attacks(square, open)
+            | SlideFill::nw_attacks(square, open)
+            | SlideFill::se_attacks(square, open)
+            | SlideFill::sw_attacks(square, open);
+
+        let attackers = (orthogonal & (white.rooks | white.queens | black.rooks | black.queens))
+            | (diagonal & (white.bishops | white.queens | black.bishops | black.queens))
+            | (knight_fill(square) & (white.knights | black.knights))
+            | (king_fill(square) & (white.king | black.king))
+            | (south_pawn_attacks(square) & white.pawns)
+            | (north_pawn_attacks(square) & black.pawns);
+        attackers & self.occupied
+    }
+
+    /// Recaptures with the least valuable attacker of `color` and returns it, or `None` if
+    /// `color` can't recapture. The king can't recapture onto a square that is still defended.
+    fn capture(&mut self, color: Color) -> Option<Piece> {
+        let pieces = match color {
+            Color::White => &self.pos.white,
+            Color::Black => &self.pos.black,
+        };
+        let (piece, board) = piece_boards(pieces)
+            .into_iter()
+            .zip(PIECES)
+            .map(|(board, piece)| (piece, board & self.attackers))
+            .find(|&(_, board)| board != 0)?;
+
+        if piece == Piece::King && self.attackers & !pieces.get_all_pieces() != 0 {
+            return None;
+        }
+
+        self.occupied &= !(board & board.wrapping_neg());
+        self.attackers = self.attackers_to();
+        Some(piece)
+    }
+}
+
+/// Every piece, in the order of `piece_boards`.
+const PIECES: [Piece; 6] = [
+    Piece::Pawn,
+    Piece::Knight,
+    Piece::Bishop,
+    Piece::Rook,
+    Piece::Queen,
+    Piece::King,
+];
@@ -0,0 +1,133 @@
+use std::fmt;
+
+use crate::position::{Color, Position};
+
+use super::{
+    eval::{game_phase, EvalTerm, Evaluator, Phased},
+    pst::MAX_PHASE,
+    Score,
+};
+
+/// A breakdown of how an evaluator scores a position, term by term, for each side and phase.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EvalTrace {
+    pub evaluator: String,
+    pub fen: String,
+    pub turn: Color,
+    /// The game phase, from `MAX_PHASE` in the opening down to 0 in a pawn ending.
+    pub phase: i32,
+    pub terms: Vec<EvalTerm>,
+    /// The evaluation for the side to move, as the search sees it.
+    pub score: Score,
+}
+
+impl EvalTrace {
+    /// Traces the evaluation of `pos` by `evaluator`.
+    pub fn new<E: Evaluator + ?Sized>(evaluator: &mut E, pos: &Position) -> EvalTrace {
+        EvalTrace {
+            evaluator: evaluator.name().to_string(),
+            fen: pos.to_fen(),
+            turn: pos.turn,
+            phase: game_phase(pos),
+            terms: evaluator.explain(pos),
+            score: evaluator.evaluate(pos),
+        }
+    }
+
+    /// Returns the evaluation from white's point of view.
+    pub fn white_score(&self) -> Score {
+        match self.turn {
+            Color::White => self.score,
+            Color::Black => -self.score,
+        }
+    }
+
+    /// Returns the trace as a single line of JSON.
+    pub fn to_json(&self) -> String {
+        let phased = |score: Phased| format!("{{\"mg\":{},\"eg\":{}}}", score.mg, score.eg);
+        let terms: Vec<String> = self
+            .terms
+            .iter()
+            .map(|term| {
+                format!(
+                    "{{\"name\":{},\"white\":{},\"black\":{}}}",
+                    json_string(&term.name),
+                    phased(term.white),
+                    phased(term.black)
+                )
+            })
+            .collect();
+        format!(
+            "{{\"evaluator\":{},\"fen\":{},\"phase\":{},\"max_phase\":{},\
+             \"terms\":[{}],\"score\":{},\"white_score\":{}}}",
+            json_string(&self.evaluator),
+            json_string(&self.fen),
+            self.phase,
+            MAX_PHASE,
+            terms.join(","),
+            self.score,
+            self.white_score()
+        )
+    }
+}
+
+/// Quotes `s` as a JSON string.
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Prints the trace as a table, with every term scored for each side and phase, and the total
+/// from white's point of view.
+impl fmt::Display for EvalTrace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const RULE: &str = "-------------+-------------+-------------+-------------";
+        let row = |f: &mut fmt::Formatter, name: &str, white: Phased, black: Phased| {
+            writeln!(
+                f,
+                "{:>12} | {:>5} {:>5} | {:>5} {:>5} | {:>5} {:>5}",
+                name,
+                white.mg,
+                white.eg,
+                black.mg,
+                black.eg,
+                white.mg - black.mg,
+                white.eg - black.eg
+            )
+        };
+
+        writeln!(f, "{} evaluation of {}", self.evaluator, self.fen)?;
+        writeln!(f, "        Term |    White    |    Black    |    Total")?;
+        writeln!(f, "             |    MG    EG |    MG    EG |    MG    EG")?;
+        writeln!(f, "{}", RULE)?;
+        let (mut white, mut black) = (Phased::default(), Phased::default());
+        for term in &self.terms {
+            row(f, &term.name, term.white, term.black)?;
+            white.mg += term.white.mg;
+            white.eg += term.white.eg;
+            black.mg += term.black.mg;
+            black.eg += term.black.eg;
+        }
+        writeln!(f, "{}", RULE)?;
+        row(f, "Total", white, black)?;
+        writeln!(f)?;
+        writeln!(f, "Phase: {}/{}", self.phase, MAX_PHASE)?;
+        write!(
+            f,
+            "Evaluation: {} (white side), {} for the side to move",
+            self.white_score(),
+            self.score
+        )
+    }
+}
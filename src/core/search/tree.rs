@@ -1,48 +1,662 @@
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
 use crate::{
-    mov::{gen::generate_legal_moves, list::MoveList, Move},
-    position::Position,
+    mov::{
+        gen::{generate_captures, generate_legal_moves, generate_moves, is_in_check, GenMode},
+        Move,
+    },
+    position::{Color, Position},
 };
 
-use super::Score;
+use super::{
+    eval::Evaluator,
+    is_mate_score,
+    limits::SearchLimits,
+    mate_distance, mated_in,
+    options::SearchOptions,
+    order::{MoveOrderer, MovePicker},
+    see::see_ge,
+    tt::{Bound, TranspositionTable, DEFAULT_HASH_MB},
+    Score, DRAW, INFINITY, MAX_DEPTH,
+};
 
-pub fn negamax<E: FnMut(&Position) -> Score>(
-    pos: &Position,
-    evaluator: &mut E,
-    depth: u8,
-) -> Score {
-    if depth == 0 {
-        return evaluator(pos);
+/// How many nodes are searched between checks of the clock and the stop flag.
+const CHECK_INTERVAL: u64 = 1024;
+
+/// How many moves are searched at full depth before late move reductions start.
+const LMR_MIN_INDEX: usize = 3;
+
+/// The shallowest search in which the TT move is tested for singularity, as the test itself
+/// costs a search of half the depth.
+const SINGULAR_MIN_DEPTH: u8 = 6;
+
+/// The deepest nodes in which quiet moves are futility pruned.
+const FUTILITY_MAX_DEPTH: u8 = 3;
+
+/// The deepest nodes that are cut off by reverse futility pruning.
+const REVERSE_FUTILITY_MAX_DEPTH: u8 = 6;
+
+/// The deepest nodes that are razored.
+const RAZORING_MAX_DEPTH: u8 = 3;
+
+/// The result of a search: the score of the position for the side to move, and the line of
+/// play the engine expects.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchResult {
+    pub score: Score,
+    /// The principal variation, starting with the best move. Empty if there are no legal
+    /// moves.
+    pub pv: Vec<Move>,
+    /// The depth of the last completed iteration.
+    pub depth: u8,
+    pub nodes: u64,
+}
+
+impl SearchResult {
+    pub fn best_move(&self) -> Option<Move> {
+        self.pv.first().copied()
+    }
+}
+
+/// Reported after every completed iteration of `iterative_deepening`, and whenever an
+/// iteration's score falls outside its aspiration window.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchInfo {
+    pub depth: u8,
+    pub score: Score,
+    /// `Exact` for a completed iteration. `Lower` after failing high, when the score is only a
+    /// lower bound and the iteration is searched again with a wider window, and `Upper` after
+    /// failing low.
+    pub bound: Bound,
+    /// The nodes searched so far, over all iterations.
+    pub nodes: u64,
+    pub elapsed: Duration,
+    pub pv: Vec<Move>,
+    /// How full the transposition table is, in permille.
+    pub hashfull: u16,
+}
+
+/// The state of a running search.
+struct Search<'a, E: ?Sized> {
+    evaluator: &'a mut E,
+    tt: &'a mut TranspositionTable,
+    orderer: MoveOrderer,
+    options: &'a SearchOptions,
+    stop: &'a AtomicBool,
+    start: Instant,
+    deadline: Option<Instant>,
+    max_nodes: Option<u64>,
+    nodes: u64,
+    /// Set once a limit is hit. The scores of an aborted search are meaningless.
+    aborted: bool,
+    /// The ply of the null move being searched, if any, so that two aren't made in a row.
+    null_move_ply: Option<u8>,
+    /// The move left out while testing whether it is singular, and the ply it is at.
+    excluded: Option<(u8, Move)>,
+    /// The depth of the current iteration, which bounds how far extensions may go.
+    root_depth: u8,
+}
+
+impl<E: Evaluator + ?Sized> Search<'_, E> {
+    /// Returns true if the search has to stop, checking the clock every few nodes. The clock
+    /// and the stop flag are only checked after the first iteration, so that there is always a
+    /// searched move to play, however little time is left.
+    fn should_abort(&mut self) -> bool {
+        if self.aborted {
+            return true;
+        }
+
+        if self.max_nodes.is_some_and(|max| self.nodes >= max) {
+            self.aborted = true;
+        } else if self.root_depth > 1 && self.nodes.is_multiple_of(CHECK_INTERVAL) {
+            self.aborted = self.stop.load(Ordering::Relaxed)
+                || self
+                    .deadline
+                    .is_some_and(|deadline| Instant::now() >= deadline);
+        }
+
+        self.aborted
+    }
+
+    /// Searches captures and promotions until the position is quiet, so that the evaluator
+    /// isn't trusted in the middle of an exchange. The side to move may stand pat on the
+    /// static evaluation instead of capturing, unless it is in check, in which case every
+    /// evasion is searched instead. `in_check` is whether it is, as the caller already knows.
+    ///
+    /// Quiescence never goes past `MAX_DEPTH`, which keeps the mate scores it returns above
+    /// `MATE_THRESHOLD`.
+    fn quiescence(
+        &mut self,
+        pos: &mut Position,
+        ply: u8,
+        in_check: bool,
+        mut alpha: Score,
+        beta: Score,
+    ) -> Score {
+        if self.should_abort() {
+            return 0;
+        }
+        self.nodes += 1;
+
+        let moves = if in_check {
+            generate_moves(pos, GenMode::Evasions)
+        } else {
+            generate_captures(pos)
+        };
+        if in_check && moves.count() == 0 {
+            return mated_in(ply);
+        }
+        if ply >= MAX_DEPTH {
+            return self.evaluator.evaluate(pos);
+        }
+
+        // a side in check can't pass, so it can't rely on the static evaluation either
+        let mut max = if in_check {
+            -INFINITY
+        } else {
+            self.evaluator.evaluate(pos)
+        };
+        if max >= beta {
+            return max;
+        }
+        alpha = alpha.max(max);
+
+        // try winning the most material first, so that bad captures are cut off
+        let picker = if in_check {
+            self.orderer.picker(pos, moves, None, ply)
+        } else {
+            MovePicker::captures(pos, moves)
+        };
+        for mov in picker {
+            // a capture that loses material can't do better than standing pat
+            if self.options.see_pruning && !in_check && !see_ge(pos, mov, 0) {
+                continue;
+            }
+
+            self.evaluator.make_move(pos, mov);
+            let undo = pos.make_move(mov);
+            let gives_check = is_in_check(pos);
+            let score = -self.quiescence(pos, ply + 1, gives_check, -beta, -alpha);
+            pos.unmake_move(mov, undo);
+            self.evaluator.unmake_move(pos, mov);
+
+            if self.aborted {
+                return 0;
+            }
+
+            if score > max {
+                max = score;
+                alpha = alpha.max(score);
+                if score >= beta {
+                    break;
+                }
+            }
+        }
+
+        max
+    }
+
+    /// Fills `pv` by following the best moves stored in the transposition table from `pos`,
+    /// for at most `depth` plies.
+    fn tt_pv(&self, pos: &Position, depth: u8, pv: &mut Vec<Move>) {
+        let mut pos = pos.clone();
+        for _ in 0..depth {
+            let Some(mov) = self.tt.probe(pos.hash, 0).and_then(|entry| entry.best_move) else {
+                break;
+            };
+            // the entry may belong to another position with the same slot and hash
+            if !generate_legal_moves(&pos).contains(&mov) {
+                break;
+            }
+            pv.push(mov);
+            pos.make_move(mov);
+        }
     }
 
-    let mut max = std::i32::MIN + 1;
-    let moves = generate_legal_moves(pos);
+    /// Fail-soft alpha-beta search to `depth` plies, `ply` plies from the root. Returns the
+    /// score of `pos` for the side to move and fills `pv` with the principal variation when
+    /// the score is inside the window.
+    fn alpha_beta(
+        &mut self,
+        pos: &mut Position,
+        depth: u8,
+        ply: u8,
+        mut alpha: Score,
+        beta: Score,
+        pv: &mut Vec<Move>,
+    ) -> Score {
+        pv.clear();
+        if depth == 0 || ply >= MAX_DEPTH {
+            let in_check = is_in_check(pos);
+            return self.quiescence(pos, ply, in_check, alpha, beta);
+        }
+
+        if self.should_abort() {
+            return 0;
+        }
+        self.nodes += 1;
+
+        // when testing a move for singularity, this node is searched without it, so its
+        // results don't belong in the table
+        let excluded = self
+            .excluded
+            .filter(|&(excluded_ply, _)| excluded_ply == ply)
+            .map(|(_, mov)| mov);
+
+        let tt_entry = self.tt.probe(pos.hash, ply);
+        if let Some(entry) = tt_entry {
+            // the root always searches, so that it has a full principal variation
+            if ply > 0 && entry.depth >= depth && excluded.is_none() {
+                let cutoff = match entry.bound {
+                    Bound::Exact => true,
+                    Bound::Lower => entry.score >= beta,
+                    Bound::Upper => entry.score <= alpha,
+                };
+                if cutoff {
+                    if entry.bound == Bound::Exact && entry.score > alpha && entry.score < beta {
+                        self.tt_pv(pos, depth, pv);
+                    }
+                    return entry.score;
+                }
+            }
+        }
+
+        let in_check = is_in_check(pos);
+        let mut child_pv = Vec::new();
+
+        // the pruning below relies on the static evaluation, which means nothing in check, and
+        // is kept out of the principal variation, where the exact score matters
+        let pv_node = beta - alpha > 1;
+        let static_eval =
+            (ply > 0 && !in_check && excluded.is_none()).then(|| self.evaluator.evaluate(pos));
+
+        if let Some(eval) = static_eval.filter(|_| !pv_node && !is_mate_score(beta)) {
+            let depth = depth as Score;
+            if self.options.reverse_futility
+                && depth <= REVERSE_FUTILITY_MAX_DEPTH as Score
+                && eval - self.options.reverse_futility_margin * depth >= beta
+            {
+                return eval;
+            }
+
+            if self.options.razoring
+                && depth <= RAZORING_MAX_DEPTH as Score
+                && eval + self.options.razoring_margin * depth < alpha
+            {
+                let score = self.quiescence(pos, ply, in_check, alpha, beta);
+                if self.aborted {
+                    return 0;
+                }
+                if score <= alpha {
+                    return score;
+                }
+            }
+        }
+
+        if self.options.null_move
+            && static_eval.is_some_and(|eval| eval >= beta)
+            && depth >= 3
+            && self.null_move_ply != Some(ply - 1)
+            && has_pieces(pos)
+        {
+            let reduction = if depth >= 6 { 3 } else { 2 };
+            let parent_null_move = self.null_move_ply.replace(ply);
+            self.evaluator.make_null_move(pos);
+            let undo = pos.make_null_move();
+            let score = -self.alpha_beta(
+                pos,
+                depth - 1 - reduction,
+                ply + 1,
+                -beta,
+                -beta + 1,
+                &mut child_pv,
+            );
+            pos.unmake_null_move(undo);
+            self.evaluator.unmake_null_move(pos);
+            self.null_move_ply = parent_null_move;
+
+            if self.aborted {
+                return 0;
+            }
+            // passing can't lead to a mate, so don't trust one
+            if score >= beta {
+                return if is_mate_score(score) { beta } else { score };
+            }
+        }
+
+        let moves = generate_legal_moves(pos);
+        if moves.count() == 0 {
+            return if in_check { mated_in(ply) } else { DRAW };
+        }
+        let tt_move = tt_entry.and_then(|entry| entry.best_move);
+
+        // the TT move is singular if every other move fails well below its score, in which case
+        // the position hinges on it and it is worth searching deeper
+        let singular_move = match (tt_entry, tt_move) {
+            (Some(entry), Some(tt_move))
+                if self.options.singular_extensions
+                    && ply > 0
+                    && depth >= SINGULAR_MIN_DEPTH
+                    && excluded.is_none()
+                    && entry.bound != Bound::Upper
+                    && entry.depth + 3 >= depth
+                    && !is_mate_score(entry.score)
+                    && self.can_extend(depth, ply) =>
+            {
+                let singular_beta = entry.score - 2 * depth as Score;
+                let parent_excluded = self.excluded.replace((ply, tt_move));
+                let score = self.alpha_beta(
+                    pos,
+                    depth / 2,
+                    ply,
+                    singular_beta - 1,
+                    singular_beta,
+                    &mut child_pv,
+                );
+                self.excluded = parent_excluded;
+
+                if self.aborted {
+                    return 0;
+                }
+                (score < singular_beta).then_some(tt_move)
+            }
+            _ => None,
+        };
 
-    for mov in moves.into_iter() {
-        let (next_pos, _) = pos.apply_move(&mov);
-        let score = -negamax(&next_pos, evaluator, depth - 1);
-        if score > max {
-            max = score;
+        // the most a quiet move is expected to gain, if it can't raise alpha
+        let futility_value = static_eval
+            .filter(|_| {
+                self.options.futility
+                    && !pv_node
+                    && depth <= FUTILITY_MAX_DEPTH
+                    && !is_mate_score(alpha)
+            })
+            .map(|eval| eval + self.options.futility_margin * depth as Score)
+            .filter(|&value| value <= alpha);
+
+        let picker = self.orderer.picker(pos, moves, tt_move, ply);
+
+        let original_alpha = alpha;
+        let mut max = -INFINITY;
+        let mut best_move = None;
+
+        for (index, mov) in picker.filter(|&mov| Some(mov) != excluded).enumerate() {
+            self.evaluator.make_move(pos, mov);
+            let undo = pos.make_move(mov);
+            let gives_check = is_in_check(pos);
+
+            if let Some(value) = futility_value {
+                if index > 0 && !mov.is_capture() && !mov.is_promotion() && !gives_check {
+                    pos.unmake_move(mov, undo);
+                    self.evaluator.unmake_move(pos, mov);
+                    max = max.max(value);
+                    continue;
+                }
+            }
+
+            // extensions are capped so that checks and singular moves can't prolong the
+            // search forever
+            let extend = self.can_extend(depth, ply)
+                && (self.options.check_extensions && gives_check || singular_move == Some(mov));
+            let depth = depth + extend as u8;
+
+            let score = if index == 0 {
+                -self.alpha_beta(pos, depth - 1, ply + 1, -beta, -alpha, &mut child_pv)
+            } else {
+                let reduction = if self.options.lmr
+                    && depth >= 3
+                    && index >= LMR_MIN_INDEX
+                    && !in_check
+                    && !mov.is_capture()
+                    && !mov.is_promotion()
+                    && !gives_check
+                {
+                    late_move_reduction(depth, index)
+                } else {
+                    0
+                };
+                // without PVS, every move gets the full window
+                let (child_alpha, child_beta) = if self.options.pvs {
+                    (-alpha - 1, -alpha)
+                } else {
+                    (-beta, -alpha)
+                };
+
+                let mut score = -self.alpha_beta(
+                    pos,
+                    depth - 1 - reduction,
+                    ply + 1,
+                    child_alpha,
+                    child_beta,
+                    &mut child_pv,
+                );
+                if reduction > 0 && score > alpha {
+                    score = -self.alpha_beta(
+                        pos,
+                        depth - 1,
+                        ply + 1,
+                        child_alpha,
+                        child_beta,
+                        &mut child_pv,
+                    );
+                }
+                if self.options.pvs && score > alpha && score < beta {
+                    score = -self.alpha_beta(pos, depth - 1, ply + 1, -beta, -alpha, &mut child_pv);
+                }
+                score
+            };
+            pos.unmake_move(mov, undo);
+            self.evaluator.unmake_move(pos, mov);
+
+            if self.aborted {
+                return 0;
+            }
+
+            if score > max {
+                max = score;
+                best_move = Some(mov);
+                if score > alpha {
+                    alpha = score;
+                    pv.clear();
+                    pv.push(mov);
+                    pv.append(&mut child_pv);
+                }
+                if score >= beta {
+                    if !mov.is_capture() && !mov.is_promotion() {
+                        self.orderer.record_cutoff(pos.turn, mov, ply, depth);
+                    }
+                    break;
+                }
+            }
         }
+
+        let bound = if max >= beta {
+            Bound::Lower
+        } else if max > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        // after failing low every move was bad, so none of them is worth trying first
+        let best_move = best_move.filter(|_| bound != Bound::Upper);
+        if excluded.is_none() {
+            self.tt.store(pos.hash, ply, depth, bound, max, best_move);
+        }
+
+        max
     }
 
-    max
+    /// Returns true if a move at `ply` may be searched deeper than `depth` allows. Lines are
+    /// extended to at most twice the depth of the iteration, and never past `MAX_DEPTH`.
+    fn can_extend(&self, depth: u8, ply: u8) -> bool {
+        ply < 2 * self.root_depth && ply + depth < MAX_DEPTH
+    }
+}
+
+/// Returns true if the side to move has anything besides pawns. In pawn endings zugzwang is
+/// common, and passing would often be the best move if it were allowed.
+fn has_pieces(pos: &Position) -> bool {
+    let pieces = match pos.turn {
+        Color::White => &pos.white,
+        Color::Black => &pos.black,
+    };
+    pieces.knights | pieces.bishops | pieces.rooks | pieces.queens != 0
 }
 
-pub fn find_best_move<E: FnMut(&Position) -> Score>(
+/// Returns how many plies less to search the move at `index`, reducing later moves and deeper
+/// searches more, but always leaving at least one ply.
+fn late_move_reduction(depth: u8, index: usize) -> u8 {
+    let reduction = 1 + (index >= 2 * LMR_MIN_INDEX) as u8 + (depth >= 6) as u8;
+    reduction.min(depth - 2)
+}
+
+/// Searches `pos` one ply deeper at a time until a limit in `limits` is hit or `stop` is set,
+/// calling `info` after every completed iteration. Returns the last completed iteration; if
+/// not even the first one completes, the first legal move is returned so there is always a
+/// move to play.
+pub fn iterative_deepening<E, I>(
     pos: &Position,
-    eval: &mut E,
-    depth: u8,
-) -> Move {
-    let mut best: (Move, i32) = (Default::default(), std::i32::MIN);
-    let moves: MoveList = generate_legal_moves(pos);
+    evaluator: &mut E,
+    tt: &mut TranspositionTable,
+    limits: &SearchLimits,
+    options: &SearchOptions,
+    stop: &AtomicBool,
+    mut info: I,
+) -> SearchResult
+where
+    E: Evaluator + ?Sized,
+    I: FnMut(&SearchInfo),
+{
+    let start = Instant::now();
+    let budget = limits.time_budget(pos.turn);
+    tt.new_search();
+    evaluator.reset(pos);
+    let mut search = Search {
+        evaluator,
+        tt,
+        orderer: MoveOrderer::new(),
+        options,
+        stop,
+        start,
+        deadline: budget.map(|budget| start + budget),
+        max_nodes: limits.nodes,
+        nodes: 0,
+        aborted: false,
+        null_move_ply: None,
+        excluded: None,
+        root_depth: 0,
+    };
+
+    let mut pos = pos.clone();
+    let mut result = SearchResult {
+        score: 0,
+        pv: generate_legal_moves(&pos).into_iter().take(1).collect(),
+        depth: 0,
+        nodes: 0,
+    };
+    if result.pv.is_empty() {
+        return result;
+    }
+
+    let mut pv = Vec::new();
+    'deepening: for depth in 1..=limits.max_depth() {
+        // the score rarely changes much between iterations, so start with a narrow window
+        // around the last one
+        let mut window = options.aspiration_window.max(1);
+        let (mut alpha, mut beta) =
+            if options.aspiration && result.depth > 0 && !is_mate_score(result.score) {
+                (result.score - window, result.score + window)
+            } else {
+                (-INFINITY, INFINITY)
+            };
+
+        search.root_depth = depth;
+        let score = loop {
+            let score = search.alpha_beta(&mut pos, depth, 0, alpha, beta, &mut pv);
+            if search.aborted {
+                break 'deepening;
+            }
 
-    for mov in moves.into_iter() {
-        let (next_pos, _) = pos.apply_move(&mov);
-        let score: i32 = negamax(&next_pos, eval, depth);
-        if score > best.1 {
-            best = (mov, score);
+            let bound = if score <= alpha {
+                Bound::Upper
+            } else if score >= beta {
+                Bound::Lower
+            } else {
+                break score;
+            };
+
+            // after failing low no move beat alpha, so the last line is still the best known
+            info(&SearchInfo {
+                depth,
+                score,
+                bound,
+                nodes: search.nodes,
+                elapsed: search.start.elapsed(),
+                pv: if bound == Bound::Lower {
+                    pv.clone()
+                } else {
+                    result.pv.clone()
+                },
+                hashfull: search.tt.hashfull(),
+            });
+
+            // widen the window on the side that failed, more each time
+            window = window.saturating_mul(2);
+            if bound == Bound::Upper {
+                alpha = score.saturating_sub(window).max(-INFINITY);
+            } else {
+                beta = score.saturating_add(window).min(INFINITY);
+            }
+        };
+
+        result.pv = pv.clone();
+        result.score = score;
+        result.depth = depth;
+        result.nodes = search.nodes;
+
+        info(&SearchInfo {
+            depth,
+            score,
+            bound: Bound::Exact,
+            nodes: search.nodes,
+            elapsed: search.start.elapsed(),
+            pv: result.pv.clone(),
+            hashfull: search.tt.hashfull(),
+        });
+
+        // the next iteration takes several times as long as this one, so don't start it if
+        // it can't finish
+        if budget.is_some_and(|budget| search.start.elapsed() * 2 > budget) {
+            break;
+        }
+
+        // a mate search is over once a short enough mate is found
+        let mate_found = mate_distance(score).is_some_and(|moves| moves > 0);
+        if mate_found && limits.mate.is_some() {
+            break;
         }
     }
 
-    best.0
+    result.nodes = search.nodes;
+    result
+}
+
+/// Searches `pos` to `depth` plies and returns the best line found.
+pub fn find_best_move<E: Evaluator + ?Sized>(
+    pos: &Position,
+    eval: &mut E,
+    depth: u8,
+) -> SearchResult {
+    iterative_deepening(
+        pos,
+        eval,
+        &mut TranspositionTable::new(DEFAULT_HASH_MB),
+        &SearchLimits::depth(depth),
+        &SearchOptions::default(),
+        &AtomicBool::new(false),
+        |_| {},
+    )
 }
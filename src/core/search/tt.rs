@@ -0,0 +1,167 @@
+use std::mem;
+
+use crate::mov::Move;
+
+use super::{Score, MATE_THRESHOLD};
+
+/// The table size used when none is configured.
+pub const DEFAULT_HASH_MB: usize = 16;
+
+/// How a stored score relates to the true score of the position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bound {
+    /// The score is exact.
+    Exact,
+    /// The search failed high: the true score is at least this.
+    Lower,
+    /// The search failed low: the true score is at most this.
+    Upper,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TtEntry {
+    /// The full hash of the position, to tell apart positions that share a slot.
+    pub key: u64,
+    pub best_move: Option<Move>,
+    /// The score, with mate scores relative to this position rather than the root.
+    pub score: Score,
+    pub depth: u8,
+    pub bound: Bound,
+    /// The search that stored the entry, so that stale entries are replaced first.
+    generation: u8,
+}
+
+/// A fixed-size hash table of search results, indexed by Zobrist hash.
+pub struct TranspositionTable {
+    entries: Vec<Option<TtEntry>>,
+    generation: u8,
+}
+
+impl TranspositionTable {
+    /// Returns an empty table using at most `size_mb` megabytes. A size of 0 disables it.
+    pub fn new(size_mb: usize) -> TranspositionTable {
+        let count = size_mb * 1024 * 1024 / mem::size_of::<Option<TtEntry>>();
+        TranspositionTable {
+            entries: vec![None; count],
+            generation: 0,
+        }
+    }
+
+    /// Resizes the table, discarding its contents.
+    pub fn resize(&mut self, size_mb: usize) {
+        *self = TranspositionTable::new(size_mb);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.fill(None);
+        self.generation = 0;
+    }
+
+    /// Marks the start of a new search, so that entries from earlier ones are replaced first.
+    pub fn new_search(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    /// Returns the number of entries the table can hold.
+    pub fn capacity(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn index(&self, key: u64) -> usize {
+        (key % self.entries.len() as u64) as usize
+    }
+
+    /// Looks up a position, converting mate scores to be relative to `ply`.
+    pub fn probe(&self, key: u64, ply: u8) -> Option<TtEntry> {
+        if self.entries.is_empty() {
+            return None;
+        }
+
+        let mut entry = self.entries[self.index(key)].filter(|entry| entry.key == key)?;
+        entry.score = score_from_tt(entry.score, ply);
+        Some(entry)
+    }
+
+    /// Stores the result of searching a position at `ply`. The slot is taken over if it is
+    /// empty, holds the same position, was written by an earlier search, or holds a shallower
+    /// result.
+    pub fn store(
+        &mut self,
+        key: u64,
+        ply: u8,
+        depth: u8,
+        bound: Bound,
+        score: Score,
+        best_move: Option<Move>,
+    ) {
+        if self.entries.is_empty() {
+            return;
+        }
+
+        let index = self.index(key);
+        let replace = match self.entries[index] {
+            None => true,
+            Some(old) => {
+                old.key == key
+                    || old.generation != self.generation
+                    || depth >= old.depth
+                    || bound == Bound::Exact && old.bound != Bound::Exact
+            }
+        };
+        if !replace {
+            return;
+        }
+
+        // keep the old best move if this search didn't find one
+        let best_move = best_move.or_else(|| {
+            self.entries[index]
+                .filter(|old| old.key == key)
+                .and_then(|old| old.best_move)
+        });
+
+        self.entries[index] = Some(TtEntry {
+            key,
+            best_move,
+            score: score_to_tt(score, ply),
+            depth,
+            bound,
+            generation: self.generation,
+        });
+    }
+
+    /// Returns how full the table is in permille, sampled from its first thousand slots as
+    /// UCI's `hashfull` expects.
+    pub fn hashfull(&self) -> u16 {
+        let sample = self.entries.len().min(1000);
+        if sample == 0 {
+            return 0;
+        }
+        let used = self.entries[..sample]
+            .iter()
+            .filter(|entry| entry.is_some_and(|entry| entry.generation == self.generation))
+            .count();
+        (used * 1000 / sample) as u16
+    }
+}
+
+/// Mate scores count plies from the root, but a stored position can be reached at any ply,
+/// so they are stored as plies from the position itself.
+fn score_to_tt(score: Score, ply: u8) -> Score {
+    if score > MATE_THRESHOLD {
+        score + ply as Score
+    } else if score < -MATE_THRESHOLD {
+        score - ply as Score
+    } else {
+        score
+    }
+}
+
+fn score_from_tt(score: Score, ply: u8) -> Score {
+    if score > MATE_THRESHOLD {
+        score - ply as Score
+    } else if score < -MATE_THRESHOLD {
+        score + ply as Score
+    } else {
+        score
+    }
+}
@@ -43,8 +43,11 @@ fn test_standard_fen() {
 
     assert!(pos.en_passant.is_none());
 
-    // test reverse (move count isn't implemented, so ignore that)
-    assert!(Position::STANDARD_FEN.starts_with(&pos.to_fen()));
+    assert_eq!(pos.halfmove_clock, 0);
+    assert_eq!(pos.fullmove_number, 1);
+
+    // test reverse
+    assert_eq!(pos.to_fen(), Position::STANDARD_FEN);
 }
 
 #[test]
@@ -53,6 +56,53 @@ fn test_fen_en_passant() {
         Position::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1".into())
             .expect("couldn't parse fen");
     assert!(pos.en_passant.is_some_and(|val| val == 20));
+
+    // the square must be on the board, behind a pawn that can have just moved two squares
+    Position::from_fen("4k3/8/8/8/8/8/8/4K3 w - e9 0 1".into())
+        .expect_err("expected from_fen to reject a square off the board");
+    Position::from_fen("4k3/8/8/8/8/8/8/4K3 w - e0 0 1".into())
+        .expect_err("expected from_fen to reject a square off the board");
+    Position::from_fen("4k3/8/8/8/8/8/8/4K3 w - z6 0 1".into())
+        .expect_err("expected from_fen to reject a square off the board");
+    Position::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e3 0 1".into())
+        .expect_err("expected from_fen to reject a square on the wrong rank for the side to move");
+    Position::from_fen("4k3/8/8/8/8/8/8/4K3 b - d6 0 1".into())
+        .expect_err("expected from_fen to reject a square on the wrong rank for the side to move");
+}
+
+#[test]
+fn test_fen_round_trip() {
+    for fen in [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        "8/8/8/8/8/8/8/K1k5 b - - 99 150",
+    ] {
+        let pos = Position::from_fen(fen.into()).expect("couldn't parse fen");
+        assert_eq!(pos.to_fen(), fen);
+    }
+}
+
+#[test]
+fn test_fen_move_counters() {
+    let pos =
+        Position::from_fen("8/8/8/8/8/8/8/K1k5 b - - 12 34".into()).expect("couldn't parse fen");
+    assert_eq!(pos.halfmove_clock, 12);
+    assert_eq!(pos.fullmove_number, 34);
+
+    // the counters may be omitted
+    let pos = Position::from_fen("8/8/8/8/8/8/8/K1k5 b - -".into()).expect("couldn't parse fen");
+    assert_eq!(pos.halfmove_clock, 0);
+    assert_eq!(pos.fullmove_number, 1);
+
+    Position::from_fen("8/8/8/8/8/8/8/K1k5 b - - x 1".into())
+        .expect_err("expected from_fen to reject a non-numeric halfmove clock");
+    Position::from_fen("8/8/8/8/8/8/8/K1k5 b - - 0 0".into())
+        .expect_err("expected from_fen to reject a zero fullmove number");
+    Position::from_fen("8/8/8/8/8/8/8/K1k5 b - - 0 99999999999".into())
+        .expect_err("expected from_fen to reject an overflowing fullmove number");
+    Position::from_fen("8/8/8/8/8/8/8/K1k5 b - - 0 1 junk".into())
+        .expect_err("expected from_fen to reject extra fields");
 }
 
 #[test]
@@ -84,6 +134,14 @@ fn test_castles() {
     .unwrap();
     assert_eq!(pos.castles, 0);
 
+    // rights without the king and rook on their home squares are dropped
+    pos = Position::from_fen("4k3/8/8/8/8/8/8/4K3 w KQkq - 0 1".into()).unwrap();
+    assert_eq!(pos.castles, 0);
+    pos = Position::from_fen("r3k3/8/8/8/8/8/8/1R2K2R w KQkq - 0 1".into()).unwrap();
+    assert_eq!(pos.castles, Castles::WHITE_KING | Castles::BLACK_QUEEN);
+    pos = Position::from_fen("r2k3r/8/8/8/8/8/8/R3K2R b KQkq - 0 1".into()).unwrap();
+    assert_eq!(pos.castles, Castles::WHITE_KING | Castles::WHITE_QUEEN);
+
     // a bunch of junk characters
     Position::from_fen(
         "rnb1kb1r/pp3p1p/2p1pnp1/q2p4/2PP4/2N1PN2/PP2BPPP/R1BQK2R w asdfhfdieERTYTREB - 0 1".into(),
@@ -80,6 +80,18 @@ fn test_west() {
     assert_eq!(occl, 0xf8f8f8f8f8f8f8f8);
 }
 
+#[test]
+fn test_west_edge() {
+    // the fill stops at the a-file instead of wrapping round to the h-file of the rank below
+    let rook: Bits = 1 << 27;
+    assert_eq!(SlideFill::west_occl(rook, !rook), 0x0f000000);
+    assert_eq!(SlideFill::west_attacks(rook, !rook), 0x07000000);
+
+    let rook: Bits = 1 << 24;
+    assert_eq!(SlideFill::west_occl(rook, !rook), rook);
+    assert_eq!(SlideFill::west_attacks(rook, !rook), 0);
+}
+
 #[test]
 fn test_ne() {
     let mut pos = Position::from_fen("Q7/Q7/Q7/Q7/Q7/Q7/Q7/QQQQQQQQ w - - 0 1".into())
@@ -0,0 +1,153 @@
+use core::{
+    game::{Game, GameResult, Termination},
+    mov::{gen::generate_legal_moves, Move},
+    position::{alg_to_index, Color, Position},
+};
+
+/// Finds the legal move between two squares, promoting to a queen if there is a choice.
+fn find_move(game: &Game, from: &str, to: &str) -> Move {
+    let from = alg_to_index(from).unwrap();
+    let to = alg_to_index(to).unwrap();
+    generate_legal_moves(game.position())
+        .into_iter()
+        .filter(|mov| mov.get_from() == from && mov.get_to() == to)
+        .last()
+        .expect("no legal move between those squares")
+}
+
+fn play(game: &mut Game, moves: &[(&str, &str)]) {
+    for (from, to) in moves {
+        let mov = find_move(game, from, to);
+        game.play(mov).expect("couldn't play move");
+    }
+}
+
+#[test]
+fn test_checkmate() {
+    let mut game = Game::from_fen(Position::STANDARD_FEN.into()).unwrap();
+    play(
+        &mut game,
+        &[("f2", "f3"), ("e7", "e5"), ("g2", "g4"), ("d8", "h4")],
+    );
+
+    assert!(game.is_checkmate());
+    assert_eq!(
+        game.result(),
+        GameResult::Win(Color::Black, Termination::Checkmate)
+    );
+    assert_eq!(game.moves().len(), 4);
+
+    // no moves can be played after the game ends
+    let mov = game.undo().unwrap();
+    assert_eq!(game.result(), GameResult::Ongoing);
+    game.play(mov).unwrap();
+    game.play(mov)
+        .expect_err("expected play to fail after checkmate");
+}
+
+#[test]
+fn test_stalemate() {
+    let game = Game::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1".into()).unwrap();
+    assert!(game.is_stalemate());
+    assert!(!game.is_checkmate());
+    assert_eq!(game.result(), GameResult::Draw(Termination::Stalemate));
+}
+
+#[test]
+fn test_repetition() {
+    let mut game = Game::from_fen(Position::STANDARD_FEN.into()).unwrap();
+    let shuffle = [("g1", "f3"), ("g8", "f6"), ("f3", "g1"), ("f6", "g8")];
+
+    play(&mut game, &shuffle);
+    assert_eq!(game.repetitions(), 2);
+    assert_eq!(game.claimable_draw(), None);
+
+    play(&mut game, &shuffle);
+    assert_eq!(game.repetitions(), 3);
+    assert_eq!(
+        game.claimable_draw(),
+        Some(Termination::ThreefoldRepetition)
+    );
+    assert_eq!(game.result(), GameResult::Ongoing);
+
+    play(&mut game, &shuffle);
+    play(&mut game, &shuffle);
+    assert_eq!(game.repetitions(), 5);
+    assert_eq!(
+        game.result(),
+        GameResult::Draw(Termination::FivefoldRepetition)
+    );
+}
+
+#[test]
+fn test_repetition_en_passant() {
+    // black can't capture on e3, so the position after 1. e4 comes back once the knights return
+    let mut game = Game::from_fen(Position::STANDARD_FEN.into()).unwrap();
+    let shuffle = [("g8", "f6"), ("g1", "f3"), ("f6", "g8"), ("f3", "g1")];
+    play(&mut game, &[("e2", "e4")]);
+    play(&mut game, &shuffle);
+    assert_eq!(game.repetitions(), 2);
+    play(&mut game, &shuffle);
+    assert_eq!(
+        game.claimable_draw(),
+        Some(Termination::ThreefoldRepetition)
+    );
+
+    // here black can, so it can't once the knights return
+    let mut game =
+        Game::from_fen("rnbqkbnr/ppp1pppp/8/8/3p4/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".into())
+            .unwrap();
+    play(&mut game, &[("e2", "e4")]);
+    play(&mut game, &shuffle);
+    assert_eq!(game.repetitions(), 1);
+}
+
+#[test]
+fn test_move_rules() {
+    let mut game = Game::from_fen("4k3/8/8/8/8/8/4P3/R3K3 w - - 99 80".into()).unwrap();
+    assert_eq!(game.claimable_draw(), None);
+    play(&mut game, &[("a1", "a2")]);
+    assert_eq!(game.claimable_draw(), Some(Termination::FiftyMoveRule));
+    assert_eq!(game.result(), GameResult::Ongoing);
+
+    let mut game = Game::from_fen("4k3/8/8/8/8/8/4P3/R3K3 w - - 149 80".into()).unwrap();
+    play(&mut game, &[("a1", "a2")]);
+    assert_eq!(
+        game.result(),
+        GameResult::Draw(Termination::SeventyFiveMoveRule)
+    );
+}
+
+#[test]
+fn test_insufficient_material() {
+    for fen in [
+        "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/4KN2 w - - 0 1",
+        "4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1",
+    ] {
+        let game = Game::from_fen(fen.into()).unwrap();
+        assert_eq!(
+            game.result(),
+            GameResult::Draw(Termination::InsufficientMaterial),
+            "{}",
+            fen
+        );
+    }
+
+    for fen in [
+        "4k3/8/8/8/8/8/8/3NKN2 w - - 0 1",
+        "4k1b1/8/8/8/8/8/8/2B1K3 w - - 0 1",
+        "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+    ] {
+        let game = Game::from_fen(fen.into()).unwrap();
+        assert!(!game.is_insufficient_material(), "{}", fen);
+    }
+}
+
+#[test]
+fn test_illegal_move() {
+    let mut game = Game::from_fen(Position::STANDARD_FEN.into()).unwrap();
+    game.play(Move::new(28, 4, 0))
+        .expect_err("expected play to reject an illegal move");
+    assert!(game.moves().is_empty());
+}
@@ -0,0 +1,184 @@
+use core::{
+    mov::{gen::generate_legal_moves, MoveFlag},
+    position::Position,
+};
+
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+#[test]
+fn test_uci_round_trip() {
+    for fen in [
+        Position::STANDARD_FEN,
+        KIWIPETE,
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    ] {
+        let pos = Position::from_fen(fen.into()).expect("couldn't parse fen");
+        for mov in generate_legal_moves(&pos).into_iter() {
+            assert_eq!(pos.parse_uci_move(&mov.to_uci()), Ok(mov));
+        }
+    }
+}
+
+#[test]
+fn test_parse_uci_move_flags() {
+    let pos = Position::from_fen(KIWIPETE.into()).expect("couldn't parse fen");
+
+    let castle = pos.parse_uci_move("e1g1").unwrap();
+    assert_eq!(castle.get_flags(), MoveFlag::KING_CASTLE);
+    assert_eq!(castle.to_uci(), "e1g1");
+    assert_eq!(
+        pos.parse_uci_move("e1c1").unwrap().get_flags(),
+        MoveFlag::QUEEN_CASTLE
+    );
+    assert!(pos.parse_uci_move("e2a6").unwrap().is_capture());
+    assert_eq!(
+        pos.parse_uci_move("a2a4").unwrap().get_flags(),
+        MoveFlag::DOUBLE_PAWN_PUSH
+    );
+
+    let pos =
+        Position::from_fen("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3".into())
+            .expect("couldn't parse fen");
+    assert!(pos.parse_uci_move("e5f6").unwrap().is_en_passant());
+
+    let pos = Position::from_fen("8/4n2P/4p3/2k1Pp2/5Pp1/6N1/3K4/8 w - - 0 1".into())
+        .expect("couldn't parse fen");
+    let promotion = pos.parse_uci_move("h7h8n").unwrap();
+    assert_eq!(
+        promotion.get_flags(),
+        MoveFlag::PROMOTION | MoveFlag::KNIGHT_PROMOTION
+    );
+    assert_eq!(promotion.to_uci(), "h7h8n");
+    assert_eq!(pos.parse_uci_move("h7h8q").unwrap().to_uci(), "h7h8q");
+}
+
+#[test]
+fn test_parse_uci_move_errors() {
+    let pos = Position::from_fen(Position::STANDARD_FEN.into()).expect("couldn't parse fen");
+    for s in [
+        "", "e2", "e2e5", "e2e4q", "e7e5", "E2E4", "i2i4", "e2e4x", "e2e4qq",
+    ] {
+        pos.parse_uci_move(s)
+            .expect_err(&format!("expected '{}' to be rejected", s));
+    }
+
+    // promotions need a piece
+    let pos = Position::from_fen("8/4n2P/4p3/2k1Pp2/5Pp1/6N1/3K4/8 w - - 0 1".into())
+        .expect("couldn't parse fen");
+    pos.parse_uci_move("h7h8")
+        .expect_err("expected a promotion without a piece to be rejected");
+}
+
+fn san(fen: &str, uci: &str) -> String {
+    let pos = Position::from_fen(fen.into()).expect("couldn't parse fen");
+    pos.move_to_san(&pos.parse_uci_move(uci).unwrap())
+}
+
+#[test]
+fn test_move_to_san() {
+    assert_eq!(san(Position::STANDARD_FEN, "e2e4"), "e4");
+    assert_eq!(san(Position::STANDARD_FEN, "g1f3"), "Nf3");
+
+    assert_eq!(san(KIWIPETE, "e1g1"), "O-O");
+    assert_eq!(san(KIWIPETE, "e1c1"), "O-O-O");
+    assert_eq!(san(KIWIPETE, "e2a6"), "Bxa6");
+    assert_eq!(san(KIWIPETE, "d5e6"), "dxe6");
+    assert_eq!(san(KIWIPETE, "e5f7"), "Nxf7");
+
+    // disambiguation by file, rank, and both
+    let knights = "1n2k3/8/5n2/8/8/8/8/4K3 b - - 0 1";
+    assert_eq!(san(knights, "b8d7"), "Nbd7");
+    assert_eq!(san(knights, "f6d7"), "Nfd7");
+    assert_eq!(san(knights, "f6e4"), "Ne4");
+    let rooks = "k7/8/8/8/8/4R3/8/4R2K w - - 0 1";
+    assert_eq!(san(rooks, "e1e2"), "R1e2");
+    assert_eq!(san(rooks, "e3e2"), "R3e2");
+    let queens = "4k3/8/8/8/8/Q7/8/Q1Q4K w - - 0 1";
+    assert_eq!(san(queens, "a1b2"), "Qa1b2");
+
+    // promotions, checks and mates
+    let promotion = "8/4n2P/4p3/2k1Pp2/5Pp1/6N1/3K4/8 w - - 0 1";
+    assert_eq!(san(promotion, "h7h8q"), "h8=Q");
+    assert_eq!(san(promotion, "h7h8n"), "h8=N");
+    assert_eq!(
+        san(
+            "rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq g3 0 2",
+            "d8h4"
+        ),
+        "Qh4#"
+    );
+    assert_eq!(san("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1", "a1a8"), "Ra8+");
+}
+
+#[test]
+fn test_san_round_trip() {
+    for fen in [
+        Position::STANDARD_FEN,
+        KIWIPETE,
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "4k3/8/8/8/8/Q7/8/Q1Q4K w - - 0 1",
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+    ] {
+        let pos = Position::from_fen(fen.into()).expect("couldn't parse fen");
+        for mov in generate_legal_moves(&pos).into_iter() {
+            assert_eq!(pos.parse_san_move(&pos.move_to_san(&mov)), Ok(mov));
+        }
+    }
+}
+
+#[test]
+fn test_parse_san_move_tolerance() {
+    let pos = Position::from_fen(KIWIPETE.into()).expect("couldn't parse fen");
+    let castle = pos.parse_uci_move("e1g1").unwrap();
+    assert_eq!(pos.parse_san_move("O-O"), Ok(castle));
+    assert_eq!(pos.parse_san_move("0-0"), Ok(castle));
+    assert_eq!(
+        pos.parse_san_move("0-0-0"),
+        Ok(pos.parse_uci_move("e1c1").unwrap())
+    );
+
+    let capture = pos.parse_uci_move("e2a6").unwrap();
+    for s in ["Bxa6", "Ba6", "Bxa6!?", "Be2xa6", "Be2-a6", "Bexa6"] {
+        assert_eq!(pos.parse_san_move(s), Ok(capture), "{}", s);
+    }
+
+    let pos = Position::from_fen("8/4n2P/4p3/2k1Pp2/5Pp1/6N1/3K4/8 w - - 0 1".into())
+        .expect("couldn't parse fen");
+    let promotion = pos.parse_uci_move("h7h8q").unwrap();
+    for s in ["h8=Q", "h8Q", "h8=Q+", "h7h8q"] {
+        assert_eq!(pos.parse_san_move(s), Ok(promotion), "{}", s);
+    }
+
+    let pos =
+        Position::from_fen("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3".into())
+            .expect("couldn't parse fen");
+    assert_eq!(
+        pos.parse_san_move("exf6 e.p."),
+        Ok(pos.parse_uci_move("e5f6").unwrap())
+    );
+}
+
+#[test]
+fn test_parse_san_move_errors() {
+    let pos =
+        Position::from_fen("1n2k3/8/5n2/8/8/8/8/4K3 b - - 0 1".into()).expect("couldn't parse fen");
+    let err = pos.parse_san_move("Nd7").unwrap_err();
+    assert!(err.starts_with("ambiguous"), "{}", err);
+    assert!(err.contains("Nbd7") && err.contains("Nfd7"), "{}", err);
+
+    let err = pos.parse_san_move("Ne5").unwrap_err();
+    assert!(err.starts_with("illegal"), "{}", err);
+    let err = pos.parse_san_move("O-O").unwrap_err();
+    assert!(err.starts_with("illegal"), "{}", err);
+
+    for s in ["", "N", "Nz9", "Nd7d", "h8=K", "@e4"] {
+        let err = pos.parse_san_move(s).unwrap_err();
+        assert!(err.starts_with("invalid"), "{}: {}", s, err);
+    }
+
+    // promotions need a piece
+    let pos = Position::from_fen("8/4n2P/4p3/2k1Pp2/5Pp1/6N1/3K4/8 w - - 0 1".into())
+        .expect("couldn't parse fen");
+    pos.parse_san_move("h8")
+        .expect_err("expected a promotion without a piece to be rejected");
+}
@@ -0,0 +1,129 @@
+use core::{
+    mov::gen::{generate_captures, generate_legal_moves, generate_moves, is_in_check, GenMode},
+    perft::{divide, perft},
+    position::Position,
+};
+
+// known node counts from https://www.chessprogramming.org/Perft_Results
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+const POSITION_3: &str = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+const POSITION_4: &str = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+const POSITION_4_MIRRORED: &str =
+    "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1";
+const POSITION_5: &str = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+const POSITION_6: &str = "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10";
+
+fn assert_perft(fen: &str, counts: &[u64]) {
+    let pos = Position::from_fen(fen.into()).expect("couldn't parse fen");
+    for (depth, &count) in counts.iter().enumerate() {
+        let depth = depth as u8 + 1;
+        assert_eq!(perft(&pos, depth), count, "perft({}) of {}", depth, fen);
+    }
+}
+
+#[test]
+fn test_perft_initial() {
+    assert_perft(Position::STANDARD_FEN, &[20, 400, 8902, 197281]);
+}
+
+#[test]
+fn test_perft_kiwipete() {
+    assert_perft(KIWIPETE, &[48, 2039, 97862]);
+}
+
+#[test]
+fn test_perft_position_3() {
+    assert_perft(POSITION_3, &[14, 191, 2812, 43238, 674624]);
+}
+
+#[test]
+fn test_perft_position_4() {
+    assert_perft(POSITION_4, &[6, 264, 9467, 422333]);
+    assert_perft(POSITION_4_MIRRORED, &[6, 264, 9467, 422333]);
+}
+
+#[test]
+fn test_perft_position_5() {
+    assert_perft(POSITION_5, &[44, 1486, 62379]);
+}
+
+#[test]
+fn test_perft_position_6() {
+    assert_perft(POSITION_6, &[46, 2079, 89890]);
+}
+
+#[test]
+fn test_divide() {
+    let pos = Position::from_fen(KIWIPETE.into()).expect("couldn't parse fen");
+    assert_eq!(divide(&pos, 2), 2039);
+    assert_eq!(divide(&pos, 1), 48);
+}
+
+// these take a while without optimizations, run them with `cargo test --release -- --ignored`
+
+#[test]
+#[ignore]
+fn test_perft_deep() {
+    assert_perft(Position::STANDARD_FEN, &[20, 400, 8902, 197281, 4865609]);
+    assert_perft(KIWIPETE, &[48, 2039, 97862, 4085603]);
+    assert_perft(POSITION_3, &[14, 191, 2812, 43238, 674624, 11030083]);
+    assert_perft(POSITION_5, &[44, 1486, 62379, 2103487]);
+    assert_perft(POSITION_6, &[46, 2079, 89890, 3894594]);
+}
+
+/// Checks that every generation mode produces exactly its share of the legal moves at every
+/// node.
+fn assert_gen_modes(pos: &mut Position, depth: u8) {
+    let legal = generate_legal_moves(pos);
+    let in_check = is_in_check(pos);
+
+    for mode in [GenMode::Captures, GenMode::Quiets, GenMode::Evasions] {
+        let generated = generate_moves(pos, mode);
+        let expected: Vec<_> = legal
+            .clone()
+            .into_iter()
+            .filter(|mov| match mode {
+                GenMode::Captures => mov.is_capture() || mov.is_promotion(),
+                GenMode::Quiets => !mov.is_capture() && !mov.is_promotion(),
+                GenMode::Evasions => in_check,
+                GenMode::All => true,
+            })
+            .collect();
+        assert_eq!(
+            generated.count(),
+            expected.len(),
+            "{:?} in {}",
+            mode,
+            pos.to_fen()
+        );
+        assert!(expected.iter().all(|mov| generated.contains(mov)));
+    }
+    assert_eq!(
+        generate_captures(pos).count(),
+        generate_moves(pos, GenMode::Captures).count()
+    );
+
+    if depth > 0 {
+        for mov in legal.into_iter() {
+            let undo = pos.make_move(mov);
+            assert_gen_modes(pos, depth - 1);
+            pos.unmake_move(mov, undo);
+        }
+    }
+}
+
+#[test]
+fn test_generation_modes() {
+    for fen in [
+        Position::STANDARD_FEN,
+        KIWIPETE,
+        POSITION_3,
+        POSITION_4,
+        POSITION_4_MIRRORED,
+        POSITION_5,
+        POSITION_6,
+    ] {
+        let mut pos = Position::from_fen(fen.into()).expect("couldn't parse fen");
+        assert_gen_modes(&mut pos, 2);
+    }
+}
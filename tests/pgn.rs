@@ -0,0 +1,128 @@
+use core::{
+    game::GameResult,
+    pgn::{PgnGame, PgnReader},
+    position::Position,
+};
+
+const IMMORTAL: &str = r#"[Event "London"]
+[Site "London ENG"]
+[Date "1851.06.21"]
+[Round "?"]
+[White "Adolf Anderssen"]
+[Black "Lionel Kieseritzky"]
+[Result "1-0"]
+
+1. e4 e5 2. f4 exf4 3. Bc4 Qh4+ 4. Kf1 b5 5. Bxb5 Nf6 6. Nf3 Qh6 7. d3 Nh5 8.
+Nh4 Qg5 9. Nf5 c6 10. g4 Nf6 11. Rg1 cxb5 12. h4 Qg6 13. h5 Qg5 14. Qf3 Ng8 15.
+Bxf4 Qf6 16. Nc3 Bc5 17. Nd5 Qxb2 18. Bd6 Bxg1 19. e5 Qxa1+ 20. Ke2 Na6 21.
+Nxg7+ Kd8 22. Qf6+ Nxf6 23. Be7# 1-0
+"#;
+
+#[test]
+fn test_parse_mainline() {
+    let game = PgnGame::parse(IMMORTAL).expect("couldn't parse pgn");
+    assert_eq!(game.tag("White"), Some("Adolf Anderssen"));
+    assert_eq!(game.result(), "1-0");
+    assert_eq!(game.mainline().len(), 45);
+
+    let played = game.to_game().expect("couldn't replay game");
+    assert!(matches!(played.result(), GameResult::Win(_, _)));
+}
+
+#[test]
+fn test_round_trip() {
+    let game = PgnGame::parse(IMMORTAL).expect("couldn't parse pgn");
+    assert_eq!(game.to_pgn(), IMMORTAL);
+    assert_eq!(PgnGame::parse(&game.to_pgn()), Ok(game));
+}
+
+#[test]
+fn test_comments_nags_and_variations() {
+    let pgn = "{Opening} 1. e4! {best by test} e5 (1... c5 $1 2. Nf3 (2. c3) 2... d6) \
+               (1... e6) 2. Nf3?! Nc6 *";
+    let game = PgnGame::parse(pgn).expect("couldn't parse pgn");
+
+    assert_eq!(game.comment.as_deref(), Some("Opening"));
+    let e4 = &game.children[0];
+    assert_eq!(e4.nags, vec![1]);
+    assert_eq!(e4.comment.as_deref(), Some("best by test"));
+    assert_eq!(e4.children.len(), 3);
+
+    let sicilian = &e4.children[1];
+    assert_eq!(sicilian.nags, vec![1]);
+    assert_eq!(sicilian.children.len(), 2);
+    assert_eq!(sicilian.children[0].children.len(), 1);
+
+    let written = game.to_pgn();
+    assert!(written.replace('\n', " ").contains(
+        "{Opening} 1. e4 $1 {best by test} 1... e5 (1... c5 $1 2. Nf3 (2. c3) 2... d6) \
+         (1... e6) 2. Nf3 $6 Nc6 *"
+    ));
+    let reparsed = PgnGame::parse(&written).expect("couldn't parse written pgn");
+    assert_eq!(reparsed.comment, game.comment);
+    assert_eq!(reparsed.children, game.children);
+}
+
+#[test]
+fn test_setup_position() {
+    let fen = "6k1/5ppp/8/8/8/8/8/R3K3 w Q - 0 1";
+    let pgn = format!("[SetUp \"1\"]\n[FEN \"{}\"]\n\n1. Ra8# 1-0", fen);
+    let game = PgnGame::parse(&pgn).expect("couldn't parse pgn");
+    assert_eq!(
+        game.start,
+        Position::from_fen(fen.into()).expect("couldn't parse fen")
+    );
+    assert!(game.to_game().expect("couldn't replay game").is_checkmate());
+
+    let black = "4k3/8/8/8/8/8/4P3/4K3 b - - 0 7";
+    let mut game = PgnGame::from_position(Position::from_fen(black.into()).unwrap());
+    assert_eq!(game.tag("FEN"), Some(black));
+    let mov = game.start.parse_san_move("Kd7").unwrap();
+    game.children.push(core::pgn::PgnNode::new(mov));
+    assert!(game.to_pgn().ends_with("\n7... Kd7 *\n"));
+}
+
+#[test]
+fn test_tag_escapes() {
+    let mut game = PgnGame::new();
+    game.set_tag("Event", "The \"Big\" \\ Game");
+    let written = game.to_pgn();
+    assert!(written.contains(r#"[Event "The \"Big\" \\ Game"]"#));
+    assert_eq!(
+        PgnGame::parse(&written).unwrap().tag("Event"),
+        Some("The \"Big\" \\ Game")
+    );
+}
+
+#[test]
+fn test_line_wrapping() {
+    let game = PgnGame::parse(IMMORTAL).unwrap();
+    assert!(game.to_pgn().lines().all(|line| line.len() <= 80));
+}
+
+#[test]
+fn test_errors() {
+    assert!(PgnGame::parse("1. e5 *").is_err());
+    assert!(PgnGame::parse("1. e4 (1. d4 *").is_err());
+    assert!(PgnGame::parse("1. e4 {unterminated").is_err());
+    assert!(PgnGame::parse("[Event \"x\"\n1. e4").is_err());
+}
+
+#[test]
+fn test_reader() {
+    let text = format!(
+        "{}\n[Event \"Second\"]\n\n1. d4 {{[not a tag]\n[Event \"still a comment\"]}} d5 *\n\n\
+         [Event \"Third\"]\n\n*\n",
+        IMMORTAL
+    );
+    let games: Vec<PgnGame> = PgnReader::new(text.as_bytes())
+        .collect::<Result<_, _>>()
+        .expect("couldn't read games");
+
+    assert_eq!(games.len(), 3);
+    assert_eq!(games[0].tag("Event"), Some("London"));
+    assert_eq!(games[1].tag("Event"), Some("Second"));
+    assert_eq!(games[1].mainline().len(), 2);
+    assert_eq!(games[2].tag("Event"), Some("Third"));
+    assert!(games[2].children.is_empty());
+}
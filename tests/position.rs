@@ -1,6 +1,7 @@
 use core::{
-    mov::{Move, MoveFlag},
-    position::Position,
+    board::Castles,
+    mov::{gen::generate_legal_moves, Move, MoveFlag},
+    position::{Piece, Position},
 };
 
 use util::{assert_fen_eq, FenSegment};
@@ -32,7 +33,12 @@ fn test_apply_move() {
     assert_fen_eq(
         &pos.to_fen(),
         "rnbqkbnr/pppppppp/8/8/8/P7/1PPPPPPP/RNBQKBNR b KQkq - 0 1",
-        vec![FenSegment::Pieces, FenSegment::Turn, FenSegment::Castles],
+        vec![
+            FenSegment::Pieces,
+            FenSegment::Turn,
+            FenSegment::Castles,
+            FenSegment::MoveCount,
+        ],
     );
 
     pos = Position::from_fen("8/4n2P/4p3/2k1Pp2/5Pp1/6N1/3K4/8 w - - 0 1".into())
@@ -51,3 +57,336 @@ fn test_apply_move() {
         vec![FenSegment::Pieces, FenSegment::Turn, FenSegment::Castles],
     );
 }
+
+#[test]
+fn test_apply_castle() {
+    let mut pos = Position::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1".into())
+        .expect("couldn't parse fen");
+    pos = pos.apply_move(&Move::new(6, 4, MoveFlag::KING_CASTLE)).0;
+    assert_fen_eq(
+        &pos.to_fen(),
+        "r3k2r/8/8/8/8/8/8/R4RK1 b kq - 0 1",
+        vec![FenSegment::Pieces, FenSegment::Turn, FenSegment::Castles],
+    );
+
+    pos = pos.apply_move(&Move::new(58, 60, MoveFlag::QUEEN_CASTLE)).0;
+    assert_fen_eq(
+        &pos.to_fen(),
+        "2kr3r/8/8/8/8/8/8/R4RK1 w - - 0 1",
+        vec![FenSegment::Pieces, FenSegment::Turn, FenSegment::Castles],
+    );
+}
+
+#[test]
+fn test_apply_en_passant() {
+    let mut pos =
+        Position::from_fen("rnbqkbnr/ppp1pppp/8/8/3p4/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".into())
+            .expect("couldn't parse fen");
+
+    // the double pawn push sets the en passant square
+    pos = pos
+        .apply_move(&Move::new(28, 12, MoveFlag::DOUBLE_PAWN_PUSH))
+        .0;
+    assert_eq!(pos.en_passant, Some(20));
+
+    let (pos, capture) =
+        pos.apply_move(&Move::new(20, 27, MoveFlag::CAPTURE | MoveFlag::EN_PASSANT));
+    assert_eq!(capture, Some(Piece::Pawn));
+    assert_fen_eq(
+        &pos.to_fen(),
+        "rnbqkbnr/ppp1pppp/8/8/8/4p3/PPPP1PPP/RNBQKBNR w KQkq - 0 1",
+        vec![
+            FenSegment::Pieces,
+            FenSegment::Turn,
+            FenSegment::Castles,
+            FenSegment::EnPassant,
+        ],
+    );
+}
+
+#[test]
+fn test_apply_revokes_castles() {
+    let mut pos = Position::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1".into())
+        .expect("couldn't parse fen");
+
+    // moving a rook loses that side's castle
+    pos = pos.apply_move(&Move::new(8, 0, MoveFlag::QUIET)).0;
+    assert_eq!(
+        pos.castles,
+        Castles::WHITE_KING | Castles::BLACK_KING | Castles::BLACK_QUEEN
+    );
+
+    // moving the king loses both
+    pos = pos.apply_move(&Move::new(59, 60, MoveFlag::QUIET)).0;
+    assert_eq!(pos.castles, Castles::WHITE_KING);
+
+    // capturing a rook loses the castle on its side
+    pos = Position::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1".into())
+        .expect("couldn't parse fen");
+    pos = pos.apply_move(&Move::new(63, 7, MoveFlag::CAPTURE)).0;
+    assert_eq!(pos.castles, Castles::WHITE_QUEEN | Castles::BLACK_QUEEN);
+}
+
+#[test]
+fn test_castle_needs_rook() {
+    let castles = |pos: &Position| {
+        generate_legal_moves(pos)
+            .into_iter()
+            .filter(|mov| mov.is_king_castle() || mov.is_queen_castle())
+            .map(|mov| mov.to_uci())
+            .collect::<Vec<_>>()
+    };
+
+    // the rights alone don't let the king castle once its rook has gone
+    let mut pos = Position::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1".into())
+        .expect("couldn't parse fen");
+    pos.white.rooks &= !(1 << 7);
+    assert_eq!(castles(&pos), vec!["e1c1"]);
+
+    pos.turn.flip();
+    pos.black.rooks &= !(1 << 56);
+    assert_eq!(castles(&pos), vec!["e8g8"]);
+}
+
+#[test]
+fn test_apply_underpromotion() {
+    let pos = Position::from_fen("8/4n2P/4p3/2k1Pp2/5Pp1/6N1/3K4/8 w - - 0 1".into())
+        .expect("couldn't parse fen");
+    let pos = pos
+        .apply_move(&Move::new(
+            63,
+            55,
+            MoveFlag::PROMOTION | MoveFlag::KNIGHT_PROMOTION,
+        ))
+        .0;
+
+    assert_fen_eq(
+        &pos.to_fen(),
+        "7N/4n3/4p3/2k1Pp2/5Pp1/6N1/3K4/8 b - - 0 1",
+        vec![FenSegment::Pieces, FenSegment::Turn],
+    );
+}
+
+#[test]
+fn test_apply_move_counters() {
+    let mut pos = Position::from_fen("r3k2r/8/8/8/8/8/4P3/R3K2R w KQkq - 5 10".into())
+        .expect("couldn't parse fen");
+
+    // quiet piece moves tick the halfmove clock, black moves tick the fullmove number
+    pos = pos.apply_move(&Move::new(3, 4, MoveFlag::QUIET)).0;
+    assert_eq!((pos.halfmove_clock, pos.fullmove_number), (6, 10));
+    pos = pos.apply_move(&Move::new(59, 60, MoveFlag::QUIET)).0;
+    assert_eq!((pos.halfmove_clock, pos.fullmove_number), (7, 11));
+
+    // pawn moves reset the clock
+    pos = pos.apply_move(&Move::new(20, 12, MoveFlag::QUIET)).0;
+    assert_eq!((pos.halfmove_clock, pos.fullmove_number), (0, 11));
+
+    // captures reset the clock
+    pos = pos.apply_move(&Move::new(7, 63, MoveFlag::CAPTURE)).0;
+    assert_eq!(pos.to_fen(), "r2k4/8/8/8/8/4P3/8/R2K3r w - - 0 12");
+}
+
+#[test]
+fn test_make_unmake_move() {
+    for fen in [
+        Position::STANDARD_FEN,
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+    ] {
+        let original = Position::from_fen(fen.into()).expect("couldn't parse fen");
+        let mut pos = original.clone();
+
+        for mov in generate_legal_moves(&original).into_iter() {
+            let (expected, capture) = original.apply_move(&mov);
+
+            let undo = pos.make_move(mov);
+            assert_eq!(pos, expected, "make_move {} differs from apply_move", mov);
+            assert_eq!(undo.capture, capture);
+
+            pos.unmake_move(mov, undo);
+            assert_eq!(pos, original, "unmake_move {} didn't restore {}", mov, fen);
+        }
+    }
+}
+
+#[test]
+fn test_null_move() {
+    let original =
+        Position::from_fen("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3".into())
+            .expect("couldn't parse fen");
+    let mut pos = original.clone();
+
+    let undo = pos.make_null_move();
+    assert_eq!(
+        pos.to_fen(),
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR b KQkq - 1 3"
+    );
+    assert_eq!(pos.hash, pos.compute_hash());
+
+    let black_undo = pos.make_null_move();
+    assert_eq!(pos.fullmove_number, 4);
+    assert_eq!(pos.hash, pos.compute_hash());
+    pos.unmake_null_move(black_undo);
+
+    pos.unmake_null_move(undo);
+    assert_eq!(pos, original);
+}
+
+fn assert_hash_consistent(pos: &mut Position, depth: u8) {
+    assert_eq!(
+        pos.hash,
+        pos.compute_hash(),
+        "stale hash in {}",
+        pos.to_fen()
+    );
+    if depth == 0 {
+        return;
+    }
+
+    for mov in generate_legal_moves(pos).into_iter() {
+        let undo = pos.make_move(mov);
+        assert_hash_consistent(pos, depth - 1);
+        pos.unmake_move(mov, undo);
+    }
+}
+
+#[test]
+fn test_zobrist_incremental() {
+    for fen in [
+        Position::STANDARD_FEN,
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    ] {
+        let mut pos = Position::from_fen(fen.into()).expect("couldn't parse fen");
+        assert_hash_consistent(&mut pos, 3);
+    }
+}
+
+#[test]
+fn test_zobrist_transposition() {
+    let start = Position::from_fen(Position::STANDARD_FEN.into()).expect("couldn't parse fen");
+
+    // Nf3 Nf6 Ng1 Ng8 returns to the starting position
+    let mut pos = start.clone();
+    for mov in [
+        Move::new(21, 6, MoveFlag::QUIET),
+        Move::new(45, 62, MoveFlag::QUIET),
+        Move::new(6, 21, MoveFlag::QUIET),
+        Move::new(62, 45, MoveFlag::QUIET),
+    ] {
+        pos.make_move(mov);
+    }
+    assert_eq!(pos.hash, start.hash);
+
+    // the side to move, castles and en passant square are all part of the hash
+    let black =
+        Position::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1".into())
+            .expect("couldn't parse fen");
+    let no_castles =
+        Position::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1".into())
+            .expect("couldn't parse fen");
+    assert_ne!(black.hash, start.hash);
+    assert_ne!(no_castles.hash, start.hash);
+
+    let ep =
+        Position::from_fen("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1".into())
+            .expect("couldn't parse fen");
+    let no_ep =
+        Position::from_fen("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1".into())
+            .expect("couldn't parse fen");
+    assert_ne!(ep.hash, no_ep.hash);
+}
+
+#[test]
+fn test_en_passant_capture() {
+    let capture = |fen: &str| {
+        Position::from_fen(fen.into())
+            .expect("couldn't parse fen")
+            .en_passant_capture()
+    };
+
+    // a pawn pinned along the diagonal it captures on may still take, but not one pinned across
+    assert_eq!(capture("1b2k3/8/8/3pP3/8/8/7K/8 w - d6 0 1"), Some(43));
+    assert_eq!(capture("4k2b/8/8/3pP3/8/8/1K6/8 w - d6 0 1"), None);
+    // nor one shielding its king along the file it leaves
+    assert_eq!(capture("4r1k1/8/8/3pP3/8/8/8/4K3 w - d6 0 1"), None);
+    // taking the pawn that gives check is fine, but other checks aren't resolved by it
+    assert_eq!(capture("4k3/8/8/3pP3/4K3/8/8/8 w - d6 0 1"), Some(43));
+    assert_eq!(capture("4k3/8/8/3pP3/8/8/8/r3K3 w - d6 0 1"), None);
+    // and the same for black
+    assert_eq!(capture("4k3/8/8/8/3Pp3/8/8/4K3 b - d3 0 1"), Some(19));
+    assert_eq!(capture("8/8/8/8/R2Pp2k/8/8/4K3 b - d3 0 1"), None);
+
+    // every position on the way agrees with the move generator
+    fn walk(pos: &mut Position, depth: u8) {
+        let moves = generate_legal_moves(pos);
+        let legal = moves.clone().into_iter().any(|mov| mov.is_en_passant());
+        assert_eq!(
+            pos.en_passant_capture().is_some(),
+            legal,
+            "{}",
+            pos.to_fen()
+        );
+        if depth > 0 {
+            for mov in moves.into_iter() {
+                let undo = pos.make_move(mov);
+                walk(pos, depth - 1);
+                pos.unmake_move(mov, undo);
+            }
+        }
+    }
+    for fen in [
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    ] {
+        let mut pos = Position::from_fen(fen.into()).expect("couldn't parse fen");
+        walk(&mut pos, 3);
+    }
+}
+
+#[test]
+fn test_zobrist_en_passant() {
+    let hash = |fen: &str| {
+        Position::from_fen(fen.into())
+            .expect("couldn't parse fen")
+            .hash
+    };
+
+    // without a pawn beside the one that moved, the en passant square makes no difference
+    let mut pos = Position::from_fen(Position::STANDARD_FEN.into()).expect("couldn't parse fen");
+    pos.make_move(Move::new(28, 12, MoveFlag::DOUBLE_PAWN_PUSH));
+    assert_eq!(pos.hash, pos.compute_hash());
+    assert_eq!(
+        pos.hash,
+        hash("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1")
+    );
+    assert_eq!(
+        pos.hash,
+        hash("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1")
+    );
+
+    // a pawn that could capture does
+    let mut pos =
+        Position::from_fen("4k3/8/8/8/3p4/8/4P3/4K3 w - - 0 1".into()).expect("couldn't parse fen");
+    pos.make_move(Move::new(28, 12, MoveFlag::DOUBLE_PAWN_PUSH));
+    assert_eq!(pos.en_passant_capture(), Some(20));
+    assert_eq!(pos.hash, pos.compute_hash());
+    assert_eq!(pos.hash, hash("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1"));
+    assert_ne!(pos.hash, hash("4k3/8/8/8/3pP3/8/8/4K3 b - - 0 1"));
+
+    // unless capturing would leave its king in check
+    let mut pos =
+        Position::from_fen("8/8/8/8/R2p3k/8/4P3/4K3 w - - 0 1".into()).expect("couldn't parse fen");
+    pos.make_move(Move::new(28, 12, MoveFlag::DOUBLE_PAWN_PUSH));
+    assert_eq!(pos.en_passant_capture(), None);
+    assert_eq!(pos.hash, pos.compute_hash());
+    assert_eq!(pos.hash, hash("8/8/8/8/R2pP2k/8/8/4K3 b - - 0 1"));
+
+    // the null move takes the en passant square back out of the hash
+    let mut pos =
+        Position::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1".into()).expect("couldn't parse fen");
+    pos.make_null_move();
+    assert_eq!(pos.hash, hash("4k3/8/8/8/3pP3/8/8/4K3 w - - 0 1"));
+}
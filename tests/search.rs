@@ -1,13 +1,860 @@
+use std::{
+    sync::atomic::AtomicBool,
+    time::{Duration, Instant},
+};
+
 use core::{
-    position::Position,
-    search::{eval::piece_evaluator, tree::find_best_move},
+    mov::{gen::generate_legal_moves, Move},
+    position::{Color, Position},
+    search::{
+        eval::{
+            game_phase, material_evaluator, piece_evaluator, pst_evaluator, Evaluator,
+            MaterialEvaluator, Phased, PstEvaluator,
+        },
+        is_mate_score,
+        limits::SearchLimits,
+        mate_distance, mate_in, mated_in,
+        options::SearchOptions,
+        order::MoveOrderer,
+        pst::MAX_PHASE,
+        see::{see, see_ge},
+        trace::EvalTrace,
+        tree::{find_best_move, iterative_deepening},
+        tt::{Bound, TranspositionTable},
+        Score, DRAW, MATE, MAX_DEPTH,
+    },
 };
 
 #[test]
 fn test_find_best_move() {
     let pos = Position::from_fen(Position::STANDARD_FEN.into()).expect("couldn't parse fen");
     println!("finding best move");
-    let best = find_best_move(&pos, &mut piece_evaluator, 5).to_string();
-    assert!(!best.is_empty());
-    println!("best move: {}", best);
+    let result = find_best_move(&pos, &mut piece_evaluator, 5);
+    assert_eq!(result.pv.len(), 5);
+    println!("best move: {}", result.best_move().expect("no best move"));
+}
+
+#[test]
+fn test_pv_is_legal() {
+    let mut pos = Position::from_fen(
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1".into(),
+    )
+    .expect("couldn't parse fen");
+    let result = find_best_move(&pos, &mut material_evaluator, 3);
+    assert_eq!(result.pv.len(), 3);
+    for mov in result.pv {
+        assert!(generate_legal_moves(&pos).contains(&mov));
+        pos.make_move(mov);
+    }
+}
+
+#[test]
+fn test_wins_material() {
+    // the queen is hanging
+    let pos =
+        Position::from_fen("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1".into()).expect("couldn't parse fen");
+    let result = find_best_move(&pos, &mut piece_evaluator, 2);
+    assert_eq!(
+        result.best_move().map(|mov| mov.to_uci()).as_deref(),
+        Some("d1d5")
+    );
+    assert_eq!(result.score, 1);
+}
+
+#[test]
+fn test_no_legal_moves() {
+    let pos =
+        Position::from_fen("7k/5QQ1/8/8/8/8/8/K7 b - - 0 1".into()).expect("couldn't parse fen");
+    let result = find_best_move(&pos, &mut piece_evaluator, 3);
+    assert!(result.pv.is_empty());
+    assert_eq!(result.best_move(), None);
+}
+
+#[test]
+fn test_stalemate_is_a_draw() {
+    // b6 stalemates black, which saves white from losing a pawn's worth of material
+    let pos =
+        Position::from_fen("k7/2K5/8/1P5p/7p/7P/8/8 w - - 0 1".into()).expect("couldn't parse fen");
+    let result = find_best_move(&pos, &mut material_evaluator, 2);
+    assert_eq!(
+        result.best_move().map(|mov| mov.to_uci()).as_deref(),
+        Some("b5b6")
+    );
+    assert_eq!(result.score, DRAW);
+}
+
+#[test]
+fn test_mate_scores() {
+    // mate on the back rank
+    let pos =
+        Position::from_fen("6k1/5ppp/8/8/8/8/8/R3K3 w Q - 0 1".into()).expect("couldn't parse fen");
+    let result = find_best_move(&pos, &mut material_evaluator, 1);
+    assert_eq!(result.score, mate_in(1));
+    assert_eq!(mate_distance(result.score), Some(1));
+
+    assert_eq!(mate_distance(mate_in(3)), Some(2));
+    assert_eq!(mate_distance(mated_in(0)), Some(0));
+    assert_eq!(mate_distance(mated_in(2)), Some(-1));
+    assert_eq!(mate_distance(-mate_in(3)), Some(-1));
+    assert_eq!(mate_distance(900), None);
+    assert!(is_mate_score(mated_in(MAX_DEPTH)));
+    assert!(!is_mate_score(DRAW));
+}
+
+#[test]
+fn test_iterative_deepening_reports_each_depth() {
+    let pos = Position::from_fen(Position::STANDARD_FEN.into()).expect("couldn't parse fen");
+    let mut depths = Vec::new();
+    let result = iterative_deepening(
+        &pos,
+        &mut piece_evaluator,
+        &mut TranspositionTable::new(1),
+        &SearchLimits::depth(4),
+        &SearchOptions::default(),
+        &AtomicBool::new(false),
+        |info| {
+            if info.bound == Bound::Exact {
+                depths.push(info.depth);
+            }
+        },
+    );
+    assert_eq!(depths, vec![1, 2, 3, 4]);
+    assert_eq!(result.depth, 4);
+}
+
+#[test]
+fn test_aspiration_windows() {
+    let pos = Position::from_fen(Position::STANDARD_FEN.into()).expect("couldn't parse fen");
+    // without a table or pruning, the window can't change the score
+    let search = |options: SearchOptions| {
+        let mut infos = Vec::new();
+        let result = iterative_deepening(
+            &pos,
+            &mut pst_evaluator,
+            &mut TranspositionTable::new(0),
+            &SearchLimits::depth(4),
+            &options,
+            &AtomicBool::new(false),
+            |info| infos.push(info.clone()),
+        );
+        (result, infos)
+    };
+
+    let (full, full_infos) = search(SearchOptions::none());
+    let (narrow, narrow_infos) = search(SearchOptions {
+        aspiration: true,
+        aspiration_window: 1,
+        ..SearchOptions::none()
+    });
+    assert_eq!(narrow.score, full.score);
+    assert!(full_infos.iter().all(|info| info.bound == Bound::Exact));
+
+    // every failed window is reported before the iteration completes
+    assert!(narrow_infos.iter().any(|info| info.bound != Bound::Exact));
+    for pair in narrow_infos.windows(2) {
+        assert!(pair[0].depth <= pair[1].depth);
+        if pair[0].bound != Bound::Exact {
+            assert_eq!(pair[0].depth, pair[1].depth);
+        }
+    }
+    let exact: Vec<Score> = narrow_infos
+        .iter()
+        .filter(|info| info.bound == Bound::Exact)
+        .map(|info| info.score)
+        .collect();
+    let full_scores: Vec<Score> = full_infos.iter().map(|info| info.score).collect();
+    assert_eq!(exact, full_scores);
+}
+
+#[test]
+fn test_limits_always_leave_a_move() {
+    let pos = Position::from_fen(Position::STANDARD_FEN.into()).expect("couldn't parse fen");
+
+    let nodes = SearchLimits {
+        nodes: Some(1),
+        ..Default::default()
+    };
+    let result = iterative_deepening(
+        &pos,
+        &mut piece_evaluator,
+        &mut TranspositionTable::new(1),
+        &nodes,
+        &SearchOptions::default(),
+        &AtomicBool::new(false),
+        |_| {},
+    );
+    assert_eq!(result.depth, 0);
+    assert!(result.best_move().is_some());
+
+    let stopped = iterative_deepening(
+        &pos,
+        &mut piece_evaluator,
+        &mut TranspositionTable::new(1),
+        &SearchLimits::default(),
+        &SearchOptions::default(),
+        &AtomicBool::new(true),
+        |_| {},
+    );
+    assert!(stopped.best_move().is_some());
+}
+
+#[test]
+fn test_movetime() {
+    let pos = Position::from_fen(Position::STANDARD_FEN.into()).expect("couldn't parse fen");
+    let limits = SearchLimits {
+        movetime: Some(Duration::from_millis(100)),
+        ..Default::default()
+    };
+
+    let start = Instant::now();
+    let result = iterative_deepening(
+        &pos,
+        &mut piece_evaluator,
+        &mut TranspositionTable::new(1),
+        &limits,
+        &SearchOptions::default(),
+        &AtomicBool::new(false),
+        |_| {},
+    );
+    assert!(start.elapsed() < Duration::from_millis(500));
+    assert!(result.depth >= 1);
+}
+
+#[test]
+fn test_time_trouble() {
+    // even with no time to think, the first iteration finishes and finds the mate
+    let pos =
+        Position::from_fen("6k1/5ppp/8/8/8/8/8/R3K3 w Q - 0 1".into()).expect("couldn't parse fen");
+    let limits = SearchLimits {
+        wtime: Some(Duration::from_millis(10)),
+        ..Default::default()
+    };
+    assert_eq!(limits.time_budget(Color::White), Some(Duration::ZERO));
+
+    let result = iterative_deepening(
+        &pos,
+        &mut material_evaluator,
+        &mut TranspositionTable::new(1),
+        &limits,
+        &SearchOptions::default(),
+        &AtomicBool::new(false),
+        |_| {},
+    );
+    assert_eq!(result.depth, 1);
+    assert_eq!(
+        result.best_move().map(|mov| mov.to_uci()).as_deref(),
+        Some("a1a8")
+    );
+}
+
+#[test]
+fn test_time_budget() {
+    let limits = SearchLimits {
+        wtime: Some(Duration::from_secs(60)),
+        btime: Some(Duration::from_millis(40)),
+        winc: Some(Duration::from_secs(1)),
+        movestogo: Some(20),
+        ..Default::default()
+    };
+    assert_eq!(
+        limits.time_budget(Color::White),
+        Some(Duration::from_millis(3750))
+    );
+    assert_eq!(limits.time_budget(Color::Black), Some(Duration::ZERO));
+    assert_eq!(SearchLimits::default().time_budget(Color::White), None);
+
+    let mate = SearchLimits {
+        mate: Some(2),
+        ..Default::default()
+    };
+    assert_eq!(mate.max_depth(), 3);
+}
+
+#[test]
+fn test_transposition_table_reduces_nodes() {
+    // an endgame, where transpositions are common
+    let pos = Position::from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1".into())
+        .expect("couldn't parse fen");
+    let search = |tt: &mut TranspositionTable| {
+        iterative_deepening(
+            &pos,
+            &mut material_evaluator,
+            tt,
+            &SearchLimits::depth(5),
+            &SearchOptions::default(),
+            &AtomicBool::new(false),
+            |_| {},
+        )
+    };
+
+    let without = search(&mut TranspositionTable::new(0));
+    let with = search(&mut TranspositionTable::new(16));
+    assert!(with.nodes < without.nodes);
+    assert_eq!(with.score, without.score);
+}
+
+#[test]
+fn test_search_options() {
+    let pos = Position::from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1".into())
+        .expect("couldn't parse fen");
+    let search = |options: SearchOptions| {
+        iterative_deepening(
+            &pos,
+            &mut material_evaluator,
+            &mut TranspositionTable::new(16),
+            &SearchLimits::depth(6),
+            &options,
+            &AtomicBool::new(false),
+            |_| {},
+        )
+    };
+
+    let none = SearchOptions::none();
+    let plain = search(none.clone());
+    for options in [
+        SearchOptions {
+            pvs: true,
+            ..none.clone()
+        },
+        SearchOptions {
+            null_move: true,
+            ..none.clone()
+        },
+        SearchOptions {
+            lmr: true,
+            ..none.clone()
+        },
+        SearchOptions {
+            see_pruning: true,
+            ..none.clone()
+        },
+        SearchOptions::default(),
+    ] {
+        let result = search(options.clone());
+        assert!(
+            result.nodes < plain.nodes,
+            "{:?} searched more nodes",
+            options
+        );
+        assert!(generate_legal_moves(&pos).contains(&result.best_move().expect("no best move")));
+    }
+}
+
+#[test]
+fn test_check_extensions() {
+    // 1. Qg8+ Rxg8 2. Nf7# is only seen at depth 2 if the checks are extended
+    let pos = Position::from_fen("4r2k/6pp/7N/3Q4/8/8/5PPP/6K1 w - - 0 1".into())
+        .expect("couldn't parse fen");
+    let search = |check_extensions: bool| {
+        iterative_deepening(
+            &pos,
+            &mut material_evaluator,
+            &mut TranspositionTable::new(1),
+            &SearchLimits::depth(2),
+            &SearchOptions {
+                check_extensions,
+                ..SearchOptions::none()
+            },
+            &AtomicBool::new(false),
+            |_| {},
+        )
+    };
+
+    let extended = search(true);
+    assert_eq!(mate_distance(extended.score), Some(2));
+    let pv: Vec<String> = extended.pv.iter().map(|mov| mov.to_uci()).collect();
+    assert_eq!(pv, ["d5g8", "e8g8", "h6f7"]);
+    assert!(!is_mate_score(search(false).score));
+}
+
+#[test]
+fn test_singular_extensions() {
+    // Rb7 is the only good move, so it is extended, and a depth 8 search scores the ending as
+    // a depth 9 search does without the extension
+    let pos = Position::from_fen("7k/p7/1R5K/6r1/6p1/6P1/8/8 w - - 0 1".into())
+        .expect("couldn't parse fen");
+    let search = |depth: u8, singular_extensions: bool| {
+        iterative_deepening(
+            &pos,
+            &mut pst_evaluator,
+            &mut TranspositionTable::new(16),
+            &SearchLimits::depth(depth),
+            &SearchOptions {
+                singular_extensions,
+                ..Default::default()
+            },
+            &AtomicBool::new(false),
+            |_| {},
+        )
+    };
+
+    let plain = search(8, false);
+    let extended = search(8, true);
+    let deeper = search(9, false);
+    assert_ne!(extended.score, plain.score);
+    assert_eq!(extended.score, deeper.score);
+    assert_eq!(
+        extended.best_move().map(|mov| mov.to_uci()).as_deref(),
+        Some("b6b7")
+    );
+}
+
+#[test]
+fn test_futility_pruning() {
+    let pos = Position::from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1".into())
+        .expect("couldn't parse fen");
+    let search = |options: SearchOptions| {
+        iterative_deepening(
+            &pos,
+            &mut pst_evaluator,
+            &mut TranspositionTable::new(16),
+            &SearchLimits::depth(6),
+            &options,
+            &AtomicBool::new(false),
+            |_| {},
+        )
+    };
+
+    // the pruning only happens outside the principal variation, so it needs PVS
+    let pvs = SearchOptions {
+        pvs: true,
+        ..SearchOptions::none()
+    };
+    let plain = search(pvs.clone());
+    for options in [
+        SearchOptions {
+            futility: true,
+            ..pvs.clone()
+        },
+        SearchOptions {
+            reverse_futility: true,
+            ..pvs.clone()
+        },
+        SearchOptions {
+            razoring: true,
+            ..pvs.clone()
+        },
+    ] {
+        let result = search(options.clone());
+        assert!(
+            result.nodes < plain.nodes,
+            "{:?} searched more nodes",
+            options
+        );
+        assert!(generate_legal_moves(&pos).contains(&result.best_move().expect("no best move")));
+    }
+}
+
+#[test]
+fn test_search_params() {
+    let mut options = SearchOptions::default();
+    let params = options.params();
+    assert!(params
+        .iter()
+        .any(|param| param.name == "FutilityMargin" && param.value == options.futility_margin));
+
+    options
+        .set_param("reversefutilitymargin", 120)
+        .expect("couldn't set parameter");
+    assert_eq!(options.reverse_futility_margin, 120);
+    assert!(options.set_param("RazoringMargin", -1).is_err());
+    assert!(options.set_param("AspirationWindow", 0).is_err());
+    assert!(options.set_param("Futility", 1).is_err());
+}
+
+#[test]
+fn test_transposition_table_entries() {
+    let mut tt = TranspositionTable::new(1);
+    assert!(tt.capacity() > 0);
+    assert_eq!(tt.probe(42, 0), None);
+
+    tt.store(42, 0, 3, Bound::Exact, 7, None);
+    let entry = tt.probe(42, 0).expect("entry wasn't stored");
+    assert_eq!(
+        (entry.depth, entry.bound, entry.score),
+        (3, Bound::Exact, 7)
+    );
+
+    // a shallower result from the same search doesn't replace a deeper one of another position
+    let other = 42 + tt.capacity() as u64;
+    tt.store(other, 0, 1, Bound::Lower, 1, None);
+    assert_eq!(tt.probe(other, 0), None);
+    tt.new_search();
+    tt.store(other, 0, 1, Bound::Lower, 1, None);
+    assert!(tt.probe(other, 0).is_some());
+    assert_eq!(tt.probe(42, 0), None);
+
+    // mate scores are stored relative to the position, not the root
+    tt.store(42, 3, 5, Bound::Exact, MATE - 5, None);
+    assert_eq!(tt.probe(42, 1).map(|entry| entry.score), Some(MATE - 3));
+    tt.store(42, 2, 5, Bound::Exact, -MATE + 4, None);
+    assert_eq!(tt.probe(42, 4).map(|entry| entry.score), Some(-MATE + 6));
+
+    tt.clear();
+    assert_eq!(tt.probe(42, 0), None);
+}
+
+#[test]
+fn test_quiescence_sees_recaptures() {
+    // the pawn on d5 is defended, so taking it loses the queen
+    let pos = Position::from_fen("4k3/8/2p5/3p4/8/8/8/3QK3 w - - 0 1".into())
+        .expect("couldn't parse fen");
+    let result = find_best_move(&pos, &mut material_evaluator, 1);
+    assert_ne!(
+        result.best_move().map(|mov| mov.to_uci()).as_deref(),
+        Some("d1d5")
+    );
+    assert_eq!(result.score, 700);
+
+    // but an undefended one can be taken
+    let pos =
+        Position::from_fen("4k3/8/8/2pp4/8/8/8/3QK3 w - - 0 1".into()).expect("couldn't parse fen");
+    let result = find_best_move(&pos, &mut material_evaluator, 1);
+    assert_eq!(
+        result.best_move().map(|mov| mov.to_uci()).as_deref(),
+        Some("d1d5")
+    );
+}
+
+#[test]
+fn test_see() {
+    let see_of = |fen: &str, mov: &str| {
+        let pos = Position::from_fen(fen.into()).expect("couldn't parse fen");
+        see(&pos, pos.parse_uci_move(mov).expect("illegal move"))
+    };
+
+    // an undefended pawn
+    assert_eq!(
+        see_of("1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1", "e1e5"),
+        100
+    );
+    // the queens behind the rook and the bishop join the exchange
+    assert_eq!(
+        see_of(
+            "1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1",
+            "d3e5"
+        ),
+        -200
+    );
+    // the king can't take back while the queen behind the rook still defends the pawn
+    assert_eq!(see_of("8/8/3k4/3p4/8/8/3R4/3QK3 w - - 0 1", "d2d5"), 100);
+    assert_eq!(see_of("8/8/3k4/3p4/8/8/3R4/4K3 w - - 0 1", "d2d5"), -400);
+    // en passant, and promotions that are taken straight back
+    assert_eq!(see_of("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6"), 100);
+    // and the same for black
+    assert_eq!(see_of("4k3/8/8/3p4/4P3/5P2/8/4K3 b - - 0 1", "d5e4"), 0);
+    assert_eq!(see_of("4k3/8/8/3q4/4P3/5P2/8/4K3 b - - 0 1", "d5e4"), -800);
+    assert_eq!(see_of("4k3/8/8/8/3pP3/8/8/5K2 b - e3 0 1", "d4e3"), 100);
+    // the rook behind the captured pawn takes back
+    assert_eq!(see_of("4k3/8/8/8/3pP3/8/8/4RK2 b - e3 0 1", "d4e3"), 0);
+    assert_eq!(see_of("3rk3/2P5/8/8/8/8/8/4K3 w - - 0 1", "c7c8q"), -100);
+    assert_eq!(see_of("3rk3/2P5/8/8/8/8/8/4K3 w - - 0 1", "c7d8q"), 400);
+
+    // see_ge agrees with see for every capture and threshold
+    for fen in [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 0 1",
+        "4k3/8/8/8/3pP3/8/8/4RK2 b - e3 0 1",
+    ] {
+        let pos = Position::from_fen(fen.into()).expect("couldn't parse fen");
+        let moves = generate_legal_moves(&pos);
+        for &mov in &moves.get_moves()[..moves.count()] {
+            for threshold in (-1000..=1000).step_by(50) {
+                assert_eq!(
+                    see_ge(&pos, mov, threshold),
+                    see(&pos, mov) >= threshold,
+                    "{} at {}",
+                    mov.to_uci(),
+                    threshold
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_losing_captures_are_ordered_last() {
+    // the pawn on d5 is defended, so taking it with the queen loses material
+    let pos = Position::from_fen("4k3/8/2p5/3p4/8/8/8/3QK3 w - - 0 1".into())
+        .expect("couldn't parse fen");
+    let order: Vec<String> = MoveOrderer::new()
+        .picker(&pos, generate_legal_moves(&pos), None, 0)
+        .map(|mov| mov.to_uci())
+        .collect();
+    assert_eq!(order.last().map(String::as_str), Some("d1d5"));
+}
+
+#[test]
+fn test_quiescence_searches_evasions() {
+    // Nxd6+ forks the king and queen, which standing pat in check would miss, and without
+    // extensions the check is only answered in quiescence
+    let pos = Position::from_fen("2q1k3/8/3p4/1N6/8/8/8/7K w - - 0 1".into())
+        .expect("couldn't parse fen");
+    let result = iterative_deepening(
+        &pos,
+        &mut material_evaluator,
+        &mut TranspositionTable::new(1),
+        &SearchLimits::depth(1),
+        &SearchOptions::none(),
+        &AtomicBool::new(false),
+        |_| {},
+    );
+    assert_eq!(
+        result.best_move().map(|mov| mov.to_uci()).as_deref(),
+        Some("b5d6")
+    );
+    // the pawn and the queen for the knight
+    assert_eq!(result.score, material_evaluator(&pos) + 700);
+}
+
+#[test]
+fn test_move_ordering() {
+    // the queen on d5 can be taken by the pawn or the rook, and the pawn on a7 can promote
+    let pos = Position::from_fen("4k3/P7/8/3q4/4P3/8/8/3RK3 w - - 0 1".into())
+        .expect("couldn't parse fen");
+    let uci = |mov: &str| pos.parse_uci_move(mov).expect("illegal move");
+
+    let mut orderer = MoveOrderer::new();
+    orderer.record_cutoff(Color::White, uci("e1f2"), 0, 3);
+    orderer.record_cutoff(Color::White, uci("d1c1"), 5, 4);
+
+    let order: Vec<String> = orderer
+        .picker(&pos, generate_legal_moves(&pos), Some(uci("e1e2")), 0)
+        .map(|mov| mov.to_uci())
+        .collect();
+
+    assert_eq!(order.len(), generate_legal_moves(&pos).count());
+    assert_eq!(order[..3], ["e1e2", "e4d5", "d1d5"]);
+    // the killer at this ply, then the move with history from another ply
+    assert_eq!(order[3..5], ["e1f2", "d1c1"]);
+    // the queen takes back on a8, so the promotions lose material and come last, best piece
+    // first
+    assert_eq!(
+        order[order.len() - 4..],
+        ["a7a8q", "a7a8r", "a7a8b", "a7a8n"]
+    );
+}
+
+#[test]
+fn test_butterfly_index() {
+    let pos = Position::from_fen(Position::STANDARD_FEN.into()).expect("couldn't parse fen");
+    let indices: std::collections::HashSet<u16> = generate_legal_moves(&pos)
+        .into_iter()
+        .map(|mov| mov.get_butterfly_index())
+        .collect();
+    assert_eq!(indices.len(), 20);
+    assert!(indices.iter().all(|&index| index < 4096));
+}
+
+#[test]
+fn test_pst_evaluator() {
+    let start = Position::from_fen(Position::STANDARD_FEN.into()).expect("couldn't parse fen");
+    assert_eq!(pst_evaluator(&start), 0);
+    assert_eq!(game_phase(&start), MAX_PHASE);
+
+    // the same position from black's side scores the same for the side to move
+    let pos = Position::from_fen(
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1".into(),
+    )
+    .expect("couldn't parse fen");
+    let mut mirrored = pos.mirror();
+    mirrored.turn = Color::Black;
+    assert_eq!(pst_evaluator(&pos), pst_evaluator(&mirrored));
+
+    // a knight is worth more in the centre than in the corner
+    let centre =
+        Position::from_fen("4k3/8/8/8/3N4/8/8/4K3 w - - 0 1".into()).expect("couldn't parse fen");
+    let corner =
+        Position::from_fen("4k3/8/8/8/8/8/8/N3K3 w - - 0 1".into()).expect("couldn't parse fen");
+    assert!(pst_evaluator(&centre) > pst_evaluator(&corner));
+    assert!(pst_evaluator(&corner) > 0);
+
+    // with only pawns left, the endgame tables are used
+    let endgame =
+        Position::from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 0 1".into()).expect("couldn't parse fen");
+    assert_eq!(game_phase(&endgame), 0);
+    assert!(pst_evaluator(&endgame) < 0);
+}
+
+#[test]
+fn test_pst_evaluator_terms() {
+    // the (white, black) scores of a term
+    let term = |fen: &str, name: &str| {
+        let pos = Position::from_fen(fen.into()).expect("couldn't parse fen");
+        let term = PstEvaluator
+            .explain(&pos)
+            .into_iter()
+            .find(|term| term.name == name)
+            .expect("missing term");
+        (term.white, term.black)
+    };
+
+    // doubled and isolated pawns are weak, passed ones strong
+    let (white, black) = term("4k3/pp6/8/8/8/4P3/4P3/4K3 w - - 0 1", "Pawns");
+    assert_eq!(white, Phased { mg: -25, eg: -40 });
+    assert_eq!(black, Phased { mg: 0, eg: 20 });
+    let (white, _) = term("4k3/5p2/8/4P3/8/8/8/4K3 w - - 0 1", "Pawns");
+    assert_eq!(white, Phased { mg: -10, eg: -15 });
+
+    // a rook moves more freely on an open file, and a knight on a square no pawn attacks
+    let (open, _) = term("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", "Mobility");
+    let (closed, _) = term("4k3/8/8/8/8/8/P7/R3K3 w - - 0 1", "Mobility");
+    assert_eq!(open, Phased { mg: 20, eg: 40 });
+    assert_eq!(closed, Phased { mg: 6, eg: 12 });
+    let (_, black) = term("4k3/8/8/3n4/8/8/3P4/4K3 w - - 0 1", "Mobility");
+    assert_eq!(black, Phased { mg: 24, eg: 24 });
+
+    // pawns shelter the king, and attacks around it weaken it, only in the middlegame
+    let (white, black) = term("6k1/5ppp/8/8/8/8/5PPP/6K1 w - - 0 1", "King safety");
+    assert_eq!(white, Phased { mg: 30, eg: 0 });
+    assert_eq!(white, black);
+    let (white, _) = term("6k1/8/8/8/8/8/5PPP/q5K1 w - - 0 1", "King safety");
+    assert_eq!(white, Phased { mg: 18, eg: 0 });
+}
+
+/// Counts material incrementally, checking that the search reports every move it makes.
+#[derive(Default)]
+struct IncrementalEvaluator {
+    /// The position the evaluator thinks the search is in.
+    pos: Option<Position>,
+    stack: Vec<Position>,
+}
+
+impl Evaluator for IncrementalEvaluator {
+    fn evaluate(&mut self, pos: &Position) -> Score {
+        assert_eq!(self.pos.as_ref().map(|pos| pos.hash), Some(pos.hash));
+        material_evaluator(pos)
+    }
+
+    fn reset(&mut self, pos: &Position) {
+        self.pos = Some(pos.clone());
+        self.stack.clear();
+    }
+
+    fn make_move(&mut self, pos: &Position, mov: Move) {
+        let current = self.pos.as_mut().expect("not reset");
+        assert_eq!(current.hash, pos.hash);
+        self.stack.push(current.clone());
+        current.make_move(mov);
+    }
+
+    fn unmake_move(&mut self, pos: &Position, _mov: Move) {
+        self.pop(pos);
+    }
+
+    fn make_null_move(&mut self, pos: &Position) {
+        let current = self.pos.as_mut().expect("not reset");
+        assert_eq!(current.hash, pos.hash);
+        self.stack.push(current.clone());
+        current.make_null_move();
+    }
+
+    fn unmake_null_move(&mut self, pos: &Position) {
+        self.pop(pos);
+    }
+}
+
+impl IncrementalEvaluator {
+    fn pop(&mut self, pos: &Position) {
+        self.pos = self.stack.pop();
+        assert_eq!(self.pos.as_ref().map(|pos| pos.hash), Some(pos.hash));
+    }
+}
+
+#[test]
+fn test_evaluator_trait() {
+    let pos = Position::from_fen(
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1".into(),
+    )
+    .expect("couldn't parse fen");
+
+    // the hooks track the search, and the result is the same as for the plain function
+    let mut incremental = IncrementalEvaluator::default();
+    let result = find_best_move(&pos, &mut incremental, 3);
+    assert!(incremental.stack.is_empty());
+    assert_eq!(result, find_best_move(&pos, &mut material_evaluator, 3));
+
+    // evaluators can be swapped at runtime
+    let mut evaluators: Vec<Box<dyn Evaluator>> = vec![
+        Box::new(MaterialEvaluator::default()),
+        Box::new(PstEvaluator),
+        Box::new(piece_evaluator),
+    ];
+    let names: Vec<&str> = evaluators.iter().map(|eval| eval.name()).collect();
+    assert_eq!(names, ["material", "pst", "custom"]);
+    for evaluator in &mut evaluators {
+        // the terms add up to the evaluation, which is for black to move here
+        let mut black = pos.clone();
+        black.turn = Color::Black;
+        let phase = game_phase(&black);
+        let total: Score = evaluator
+            .explain(&black)
+            .iter()
+            .map(|term| term.white.taper(phase) - term.black.taper(phase))
+            .sum();
+        assert_eq!(-total, evaluator.evaluate(&black));
+        let start = Position::from_fen(Position::STANDARD_FEN.into()).expect("couldn't parse fen");
+        assert!(find_best_move(&start, evaluator.as_mut(), 1)
+            .best_move()
+            .is_some());
+    }
+}
+
+#[test]
+fn test_evaluator_params() {
+    let mut material = MaterialEvaluator::default();
+    assert_eq!(material.params().len(), 5);
+    assert_eq!(material.params()[1].value, 300);
+
+    let pos =
+        Position::from_fen("4k3/8/8/8/8/8/8/1N2K3 w - - 0 1".into()).expect("couldn't parse fen");
+    material
+        .set_param("knight", 325)
+        .expect("couldn't set knight");
+    assert_eq!(material.evaluate(&pos), 325);
+    assert!(material.set_param("Knight", -1).is_err());
+    assert!(material.set_param("King", 100).is_err());
+    assert!(PstEvaluator.set_param("Pawn", 100).is_err());
+}
+
+#[test]
+fn test_eval_trace() {
+    let pos =
+        Position::from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 0 1".into()).expect("couldn't parse fen");
+    let trace = EvalTrace::new(&mut PstEvaluator, &pos);
+    assert_eq!(trace.phase, 0);
+    assert_eq!(trace.score, pst_evaluator(&pos));
+    assert_eq!(trace.white_score(), -trace.score);
+    let names: Vec<&str> = trace.terms.iter().map(|term| term.name.as_str()).collect();
+    assert_eq!(
+        names,
+        ["Material", "Placement", "Pawns", "Mobility", "King safety"]
+    );
+    assert_eq!(trace.terms[0].black.eg, 0);
+
+    let table = trace.to_string();
+    assert!(table.contains("Material"));
+    assert!(table.ends_with(&format!(
+        "Evaluation: {} (white side), {} for the side to move",
+        trace.white_score(),
+        trace.score
+    )));
+
+    let json = trace.to_json();
+    assert!(json.starts_with(r#"{"evaluator":"pst","fen":"4k3/8/8/8/8/8/4P3/4K3 b - - 0 1","#));
+    assert!(
+        json.contains(r#"{"name":"Material","white":{"mg":82,"eg":94},"black":{"mg":0,"eg":0}}"#)
+    );
+    // the pawn is isolated, but passed
+    assert!(json.contains(r#"{"name":"Pawns","white":{"mg":-10,"eg":-5},"black":{"mg":0,"eg":0}}"#));
+    assert!(json.ends_with(&format!(
+        r#""score":{},"white_score":{}}}"#,
+        trace.score, -trace.score
+    )));
+
+    // any evaluator can be traced
+    let trace = EvalTrace::new(&mut piece_evaluator, &pos);
+    assert_eq!(trace.evaluator, "custom");
+    assert_eq!(trace.terms.len(), 1);
 }
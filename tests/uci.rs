@@ -0,0 +1,266 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    process::{Command, Stdio},
+};
+
+use core::position::Position;
+
+/// Runs the engine binary with the given input and returns its output lines.
+fn run_engine(input: &str) -> Vec<String> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_cheddar"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("couldn't start engine");
+
+    child
+        .stdin
+        .take()
+        .expect("no stdin")
+        .write_all(input.as_bytes())
+        .expect("couldn't write to engine");
+
+    let output = child.wait_with_output().expect("engine didn't exit");
+    assert!(output.status.success());
+    String::from_utf8(output.stdout)
+        .expect("output isn't utf-8")
+        .lines()
+        .map(|line| line.to_string())
+        .collect()
+}
+
+/// Like `run_engine`, but waits for the engine to report its best move before quitting, as
+/// quitting stops the search.
+fn run_engine_to_bestmove(input: &str) -> Vec<String> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_cheddar"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("couldn't start engine");
+
+    let mut stdin = child.stdin.take().expect("no stdin");
+    stdin
+        .write_all(input.as_bytes())
+        .expect("couldn't write to engine");
+
+    let mut output = Vec::new();
+    let stdout = BufReader::new(child.stdout.take().expect("no stdout"));
+    for line in stdout.lines() {
+        let line = line.expect("output isn't utf-8");
+        let done = line.starts_with("bestmove ");
+        output.push(line);
+        if done {
+            break;
+        }
+    }
+
+    stdin
+        .write_all(b"quit\n")
+        .expect("couldn't write to engine");
+    drop(stdin);
+    assert!(child.wait().expect("engine didn't exit").success());
+    output
+}
+
+fn best_move(output: &[String]) -> &str {
+    output
+        .iter()
+        .find_map(|line| line.strip_prefix("bestmove "))
+        .expect("no bestmove")
+}
+
+#[test]
+fn test_handshake() {
+    let output = run_engine("uci\nisready\nquit\n");
+    assert!(output[0].starts_with("id name cheddar"));
+    assert!(output.iter().any(|line| line == "uciok"));
+    assert_eq!(output.last().map(String::as_str), Some("readyok"));
+}
+
+#[test]
+fn test_go_from_moves() {
+    let output = run_engine("position startpos moves e2e4 e7e5\ngo depth 1\nquit\n");
+    let mov = best_move(&output);
+
+    let mut pos = Position::from_fen(Position::STANDARD_FEN.into()).unwrap();
+    for mov in ["e2e4", "e7e5"] {
+        let mov = pos.parse_uci_move(mov).unwrap();
+        pos.make_move(mov);
+    }
+    assert!(pos.parse_uci_move(mov).is_ok());
+}
+
+#[test]
+fn test_go_from_fen() {
+    // black's only legal move is to take the rook
+    let output = run_engine(
+        "position fen 7k/6R1/8/8/8/8/8/K6R b - - 0 1 moves\n\
+         go wtime 1000 btime 1000 winc 10 binc 10 depth 1\nquit\n",
+    );
+    assert_eq!(best_move(&output), "h8g7");
+}
+
+#[test]
+fn test_infinite_waits_for_stop() {
+    let output = run_engine("position startpos\ngo infinite\nisready\nstop\nquit\n");
+    let ready = output.iter().position(|line| line == "readyok").unwrap();
+    let best = output
+        .iter()
+        .position(|line| line.starts_with("bestmove"))
+        .unwrap();
+    assert!(ready < best);
+}
+
+#[test]
+fn test_errors() {
+    let output = run_engine(
+        "position fen nonsense\nposition fen 4k3/8/8/8/8/8/8/4K3 w - e9 0 1\n\
+         position startpos moves e2e5\nfoo\nquit\n",
+    );
+    assert_eq!(output.len(), 4);
+    assert!(output.iter().all(|line| line.starts_with("info string")));
+}
+
+#[test]
+fn test_go_movetime() {
+    let start = std::time::Instant::now();
+    let output = run_engine("position startpos\ngo movetime 200\nisready\n");
+    assert!(start.elapsed() < std::time::Duration::from_secs(2));
+    assert!(output.iter().any(|line| line.starts_with("info depth 1 ")));
+    best_move(&output);
+}
+
+#[test]
+fn test_stop_cuts_search_short() {
+    let start = std::time::Instant::now();
+    let output = run_engine("position startpos\ngo depth 60\nisready\nstop\nquit\n");
+    assert!(start.elapsed() < std::time::Duration::from_secs(2));
+    best_move(&output);
+}
+
+#[test]
+fn test_go_nodes() {
+    let output = run_engine_to_bestmove("position startpos\ngo nodes 2000\n");
+    let nodes: Vec<u64> = output
+        .iter()
+        .filter_map(|line| line.split(" nodes ").nth(1))
+        .filter_map(|rest| rest.split(' ').next()?.parse().ok())
+        .collect();
+    assert!(!nodes.is_empty());
+    assert!(nodes.iter().all(|&nodes| nodes <= 2000));
+    best_move(&output);
+}
+
+#[test]
+fn test_go_in_time_trouble() {
+    let output =
+        run_engine("position fen 6k1/5ppp/8/8/8/8/8/R3K3 w Q - 0 1\ngo wtime 10 btime 10\nquit\n");
+    assert!(output.iter().any(|line| line.starts_with("info depth 1 ")));
+    assert_eq!(best_move(&output), "a1a8");
+}
+
+#[test]
+fn test_hash_option() {
+    let output = run_engine(
+        "uci\nsetoption name Hash value 1\nsetoption name Hash value 0\n\
+         position startpos\ngo depth 2\nquit\n",
+    );
+    assert!(output
+        .iter()
+        .any(|line| line.starts_with("option name Hash")));
+    assert!(output
+        .iter()
+        .any(|line| line == "info string invalid Hash size: 0"));
+    best_move(&output);
+}
+
+#[test]
+fn test_search_options() {
+    let output = run_engine(
+        "uci\nsetoption name LMR value false\nsetoption name NullMove value no\n\
+         position startpos\ngo depth 3\nquit\n",
+    );
+    for name in [
+        "PVS",
+        "NullMove",
+        "LMR",
+        "Aspiration",
+        "CheckExtensions",
+        "SingularExtensions",
+        "Futility",
+        "ReverseFutility",
+        "Razoring",
+        "SeePruning",
+    ] {
+        let option = format!("option name {} type check default true", name);
+        assert!(output.iter().any(|line| line.starts_with(&option)));
+    }
+    assert!(output
+        .iter()
+        .any(|line| line == "info string invalid NullMove value: no"));
+    best_move(&output);
+}
+
+#[test]
+fn test_search_params() {
+    let output = run_engine(
+        "uci\nsetoption name FutilityMargin value 150\n\
+         setoption name RazoringMargin value 5000\nsetoption name futilitymargin value -5\n\
+         setoption name lmr value maybe\nposition startpos\ngo depth 3\nquit\n",
+    );
+    for name in ["FutilityMargin", "ReverseFutilityMargin", "RazoringMargin"] {
+        let option = format!("option name {} type spin", name);
+        assert!(output.iter().any(|line| line.starts_with(&option)));
+    }
+    assert!(output
+        .iter()
+        .any(|line| line == "info string invalid RazoringMargin: 5000"));
+    // names aren't case sensitive
+    assert!(output
+        .iter()
+        .any(|line| line == "info string invalid futilitymargin: -5"));
+    assert!(output
+        .iter()
+        .any(|line| line == "info string invalid lmr value: maybe"));
+    best_move(&output);
+}
+
+#[test]
+fn test_aspiration_bounds() {
+    let output = run_engine_to_bestmove(
+        "setoption name AspirationWindow value 1\nsetoption name AspirationWindow value 0\n\
+         position startpos\ngo depth 5\n",
+    );
+    assert!(output
+        .iter()
+        .any(|line| line == "info string invalid AspirationWindow: 0"));
+    assert!(output
+        .iter()
+        .any(|line| line.contains(" lowerbound ") || line.contains(" upperbound ")));
+    best_move(&output);
+}
+
+#[test]
+fn test_eval() {
+    let output = run_engine("position startpos moves e2e4\neval\neval json\nquit\n");
+    assert!(output[0].starts_with("pst evaluation of "));
+    assert!(output
+        .iter()
+        .any(|line| line.trim_start().starts_with("Placement |")));
+    assert!(output
+        .iter()
+        .any(|line| line.starts_with("Evaluation: ") && line.ends_with("for the side to move")));
+    assert!(output
+        .last()
+        .is_some_and(|line| line.starts_with(r#"{"evaluator":"pst""#)));
+}
+
+#[test]
+fn test_go_mate() {
+    let output =
+        run_engine_to_bestmove("position fen 6k1/5ppp/8/8/8/8/8/R3K3 w Q - 0 1\ngo mate 1\n");
+    assert!(output
+        .iter()
+        .any(|line| line.contains(" score mate 1 ") && line.ends_with(" pv a1a8")));
+    assert_eq!(best_move(&output), "a1a8");
+}
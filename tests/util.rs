@@ -13,35 +13,21 @@ struct SplitFen<'a> {
     turn: &'a str,
     castles: &'a str,
     en_passant: &'a str,
-    move_count: &'a str,
+    halfmove_clock: &'a str,
+    fullmove_number: &'a str,
 }
 
-fn get_fen_segments(s: &str) -> SplitFen {
-    let mut tokens: Vec<&str> = s.split_ascii_whitespace().collect();
-
-    let mut move_count_half = false;
-    let mut token_start = 0;
-    for (i, c) in s.chars().enumerate() {
-        if c == ' ' {
-            // skip the space in the middle of the move count
-            if tokens.len() == 4 && !move_count_half {
-                move_count_half = true;
-                continue;
-            }
-
-            if i - token_start > 1 {
-                tokens.push(&s[token_start..i]);
-            }
-            token_start = i + 1;
-        }
-    }
+fn get_fen_segments(s: &str) -> SplitFen<'_> {
+    let tokens: Vec<&str> = s.split_ascii_whitespace().collect();
+    assert_eq!(tokens.len(), 6, "expected a six-field fen: \"{}\"", s);
 
     SplitFen {
         pieces: tokens[0],
         turn: tokens[1],
         castles: tokens[2],
         en_passant: tokens[3],
-        move_count: tokens[4],
+        halfmove_clock: tokens[4],
+        fullmove_number: tokens[5],
     }
 }
 
@@ -56,7 +42,11 @@ pub fn assert_fen_eq(a: &str, b: &str, segments: Vec<FenSegment>) {
             FenSegment::Turn => success = success && a_split.turn == b_split.turn,
             FenSegment::Castles => success = success && a_split.castles == b_split.castles,
             FenSegment::EnPassant => success = success && a_split.en_passant == b_split.en_passant,
-            FenSegment::MoveCount => success = success && a_split.move_count == b_split.move_count,
+            FenSegment::MoveCount => {
+                success = success
+                    && a_split.halfmove_clock == b_split.halfmove_clock
+                    && a_split.fullmove_number == b_split.fullmove_number
+            }
         }
     }
 